@@ -7,53 +7,57 @@ use crate::{prelude::*, colored_graph::neighborhood::*};
 use crate::colored_graph::*;
 
 use itertools::Itertools;
-use rand::rngs::ThreadRng;
+use rand::Rng;
 
 
 pub type EdgePos = usize;
 pub type Action = (Color, EdgePos);
 
 #[derive(Clone)]
-pub struct ActionMatrix<T: Neighborhood, const C: usize, const N: usize, const E: usize> {
-    pub(crate) counts: [[Iyy; E]; C],
-    pub(crate) graph: ColoredGraph<T, C, N>,
+pub struct ActionMatrix<T: Neighborhood, const C: usize> {
+    /* a row of one count per edge for each color, on the heap since they outgrow a stack at large n */
+    pub(crate) counts: [Vec<Iyy>; C],
+    pub(crate) graph: ColoredGraph<T, C>,
     pub(crate) actions: PriorityQueue<Action, Iyy>,
     pub(crate) totals: [Iyy; C],
+    pub(crate) s: [usize; C],
     pub(crate) phantom: PhantomData<T>
 }
 
-impl<T: Neighborhood, const C: usize, const N: usize, const E: usize> From<ColoredGraph<T, C, N>> for ActionMatrix<T, C, N, E> {
-    fn from(graph: ColoredGraph<T, C, N>) -> Self {
-        let mut counts: [[Iyy; E]; C] = [[0; E]; C];
+impl<T: Neighborhood, const C: usize>
+ActionMatrix<T, C> {
+    pub fn new(graph: ColoredGraph<T, C>, s: [usize; C]) -> Self {
+        let mut counts: [Vec<Iyy>; C] = std::array::from_fn(|_| vec![0; choose_two(graph.n())]);
         let mut actions: PriorityQueue<Action, Iyy> = Default::default();
         let mut totals: [Iyy; C] = [0; C];
-        for (pos, (u, v)) in (0..N)
+        for (pos, (u, v)) in (0..graph.n())
             .tuple_combinations().enumerate()
         {
             let old_color = graph.color((u, v)).unwrap();
-            let old_count = graph.count_edge_cliques(old_color, (u,v));
+            let old_count = graph.count_edge_cliques(old_color, s[old_color], (u,v));
             totals[old_color] += old_count;
             counts[old_color][pos] = old_count;
             for (new_color, color_counts) in counts.iter_mut().enumerate() {
                 if new_color != old_color {
-                    let new_count = graph.count_edge_cliques(new_color, (u,v));
+                    let new_count = graph.count_edge_cliques(new_color, s[new_color], (u,v));
                     color_counts[pos] = new_count;
                     actions.push((new_color, pos), old_count - new_count);
                 }
             }
         }
 
-        for c in 0..C {
-            totals[c] /= choose(S[c], 2)
+        for (total, &s) in totals.iter_mut().zip(s.iter()) {
+            *total /= choose(s, 2)
         }
 
-        ActionMatrix { counts, graph, actions, totals, phantom: PhantomData }
+        ActionMatrix { counts, graph, actions, totals, s, phantom: PhantomData }
     }
 }
 
-impl<T: Neighborhood, const C: usize, const N: usize, const E: usize>
-ActionMatrix<T, C, N, E> {
-    pub fn graph(&self) -> &ColoredGraph<T, C, N> { &self.graph }
+impl<T: Neighborhood, const C: usize>
+ActionMatrix<T, C> {
+    pub fn graph(&self) -> &ColoredGraph<T, C> { &self.graph }
+    pub fn s(&self) -> &[usize; C] { &self.s }
     pub fn actions_mut(&mut self) -> &mut PriorityQueue<Action, Iyy> { &mut self.actions }
     pub fn slope(&self, action: Action) -> Option<&Iyy> {
         self.actions.get_priority(&action)
@@ -81,7 +85,7 @@ ActionMatrix<T, C, N, E> {
             }
         }
 
-        let edge = pos_to_edge::<N>(pos);
+        let edge = pos_to_edge(self.graph.n(), pos);
         self.delete(old_color, edge);
         self.add(new_color, edge);
 
@@ -100,7 +104,7 @@ ActionMatrix<T, C, N, E> {
     fn toggle<const IS_DELETION: bool>
     (&mut self, color: Color, (u, v): Edge)
     {
-        let s = S[color];
+        let s = self.s[color];
         if s < 3 { return }
 
         let neighbors_uv = self.graph.common_neighborhood(color, u, v);
@@ -109,7 +113,7 @@ ActionMatrix<T, C, N, E> {
             neighbors_u.delete(v);
             for w in neighbors_u.iter() {
                 let neighbors_uvw = neighbors_uv & self.graph.bit_neighborhood(color, w);
-                let count_uvw = self.graph.count_cliques(color, Some(s-3), Some(neighbors_uvw));
+                let count_uvw = self.graph.count_cliques(color, s-3, Some(neighbors_uvw));
                 self.adjust_count::<IS_DELETION>(color, (v,w), count_uvw)
             }
         }
@@ -118,7 +122,7 @@ ActionMatrix<T, C, N, E> {
         
         for (w, x) in neighbors_uv.iter().tuple_combinations() {
             let candidates = neighbors_uv & self.graph.common_neighborhood(color, w, x);
-            let count_uvwx = self.graph.count_cliques(color, Some(s-4), Some(candidates));
+            let count_uvwx = self.graph.count_cliques(color, s-4, Some(candidates));
             self.adjust_count::<IS_DELETION>(color, (w,x), count_uvwx)
         }
     }
@@ -131,7 +135,7 @@ ActionMatrix<T, C, N, E> {
     }
 
     fn decrement_count(&mut self, color: Color, edge: Edge, amount: Iyy) {
-        let pos = edge_to_pos::<N>(edge);
+        let pos = edge_to_pos(self.graph.n(), edge);
         self.counts[color][pos] -= amount;
         let curr_color = self.graph.color(edge).unwrap();
         if curr_color == color {
@@ -153,7 +157,7 @@ ActionMatrix<T, C, N, E> {
     }
 
     fn increment_count(&mut self, color: Color, edge: Edge, amount: Iyy) {
-        let pos = edge_to_pos::<N>(edge);
+        let pos = edge_to_pos(self.graph.n(), edge);
         self.counts[color][pos] += amount;
         let curr_color = self.graph.color(edge).unwrap();
         if curr_color == color {
@@ -175,20 +179,20 @@ ActionMatrix<T, C, N, E> {
     }
 }
 
-impl<const N: usize> From<&Recoloring<N>> for Action {
-    fn from(recoloring: &Recoloring<N>) -> Self {
-        let pos = edge_to_pos::<N>(recoloring.edge);
-        (recoloring.new_color, pos)
+impl Recoloring {
+    /* as an action on a coloring of K_n */
+    pub fn action(&self, n: usize) -> Action {
+        (self.new_color, edge_to_pos(n, self.edge))
     }
 }
 
-impl<T: Neighborhood, const C: usize, const N: usize, const E: usize>
-ActionMatrix<T, C, N, E> {
+impl<T: Neighborhood, const C: usize>
+ActionMatrix<T, C> {
     pub fn score(&self) -> Iyy {
         let mut score: Iyy = 0;
-        for (color, &s) in S.iter().enumerate() {
+        for (color, &s) in self.s.iter().enumerate() {
             let mut color_score: Iyy = 0;
-            for (pos, (u,v)) in (0..N).tuple_combinations().enumerate() {
+            for (pos, (u,v)) in (0..self.graph.n()).tuple_combinations().enumerate() {
                 let colored_edge = ColoredEdge { color, edge: (u, v) };
                 if self.graph.has_edge(colored_edge) {
                     color_score += self.counts[color][pos]
@@ -204,7 +208,7 @@ ActionMatrix<T, C, N, E> {
     }
 
     pub fn act(&mut self, (new_color, pos): Action) {
-        let edge = pos_to_edge::<N>(pos);
+        let edge = pos_to_edge(self.graph.n(), pos);
         let old_color = self.graph.color(edge)
             .unwrap();
         self.recolor((new_color, pos), old_color);
@@ -212,16 +216,16 @@ ActionMatrix<T, C, N, E> {
         self.graph.recolor(recoloring);
     }
 
-    pub fn randomly_act(&mut self, rng: &mut ThreadRng) {
+    pub fn randomly_act<R: Rng + ?Sized>(&mut self, rng: &mut R) {
         let recoloring = self.graph.random_recoloring(rng);
-        let action = Action::from(&recoloring);
+        let action = recoloring.action(self.graph.n());
         self.recolor(action, recoloring.old_color);
         self.graph.recolor(recoloring)
     }
 }
 
-impl<T: Neighborhood, const C: usize, const N: usize, const E: usize>
-PartialEq for ActionMatrix<T, C, N, E> {
+impl<T: Neighborhood, const C: usize>
+PartialEq for ActionMatrix<T, C> {
     fn eq(&self, other: &Self) -> bool {
         self.graph == other.graph
     }
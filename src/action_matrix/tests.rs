@@ -1,88 +1,105 @@
 #[cfg(test)]
 mod action_matrix_initialization {
-    use crate::{prelude::{choose, choose_two, S}, colored_graph::{neighborhood::UxxN, ColoredGraph}, action_matrix::ActionMatrix};
-    
-    const C: usize = 2;
+    use crate::{prelude::{choose, choose_two}, colored_graph::{neighborhood::U8N, ColoredGraph}, action_matrix::ActionMatrix};
+
     const N: usize = 8;
     const E: usize = choose_two(N);
 
-    type T = UxxN<N>;
+    type T = U8N;
 
-    #[test]
-    fn correct_number_of_acounts() {
-        let graph = ColoredGraph::<T, C, N>::red();
-        let actions = ActionMatrix::<T, C, N, E>::from(graph);
+    fn check_number_of_actions<const C: usize>(s: [usize; C]) {
+        let graph = ColoredGraph::<T, C>::red(N);
+        let actions = ActionMatrix::<T, C>::new(graph, s);
         assert_eq!(actions.actions.len(), (C-1) * E)
     }
 
-    #[test]
-    fn red_graph_action_gradients() {
-        let graph = ColoredGraph::<T, C, N>::red();
-        let actions = ActionMatrix::<T, C, N, E>::from(graph);
+    fn check_red_graph_gradients<const C: usize>(s: [usize; C]) {
+        let graph = ColoredGraph::<T, C>::red(N);
+        let actions = ActionMatrix::<T, C>::new(graph, s);
         for ((color, _), slope) in actions.actions {
             assert_ne!(color, 0);
-            assert_eq!(slope, choose(N-2, S[0]-2));
+            assert_eq!(slope, choose(N-2, s[0]-2));
         }
     }
+
+    #[test]
+    fn correct_number_of_acounts() {
+        check_number_of_actions([3, 3]);
+        check_number_of_actions([4, 4]);
+        check_number_of_actions([3, 5]);
+        check_number_of_actions([3, 3, 3]);
+    }
+
+    #[test]
+    fn red_graph_action_gradients() {
+        check_red_graph_gradients([3, 3]);
+        check_red_graph_gradients([4, 4]);
+        check_red_graph_gradients([3, 5]);
+        check_red_graph_gradients([3, 3, 3]);
+    }
 }
 
 #[cfg(test)]
 mod recolor_gradient_test {
     use itertools::Itertools;
 
-    use crate::{prelude::{choose, choose_two, S}, colored_graph::{neighborhood::UxxN, ColoredGraph}, action_matrix::ActionMatrix};
-    
-    const C: usize = 2;
+    use crate::{prelude::choose, colored_graph::{neighborhood::U8N, ColoredGraph}, action_matrix::ActionMatrix};
+
     const N: usize = 8;
-    const E: usize = choose_two(N);
 
-    type T = UxxN<N>;
+    type T = U8N;
 
-    #[test]
-    fn one_recoloring() {
-        let mut actions = ActionMatrix::<T, C, N, E>::from(ColoredGraph::<T, C, N>::red());
+    fn check_one_recoloring<const C: usize>(s: [usize; C]) {
+        let mut actions = ActionMatrix::<T, C>::new(ColoredGraph::<T, C>::red(N), s);
         actions.recolor((1, 0), 0);
         for (i, (u,v)) in (0..N).tuple_combinations().enumerate() {
             let slope_0 = actions.slope((0, i));
             let slope_1 = actions.slope((1, i));
-            
+
             match (u, v) {
                 (0, 1) => assert_eq!(slope_1, None),
-                (0, _) | (1, _) => assert_eq!(slope_1, Some(&choose(N-3, S[0]-2))),
+                (0, _) | (1, _) => assert_eq!(slope_1, Some(&choose(N-3, s[0]-2))),
                 (_, _) => assert_eq!(slope_1, Some(&(
-                    choose(N-2, S[0]-2) - 
-                        if N >= 4 && S[0] >= 4 { choose(N-4, S[0].checked_sub(4).unwrap()) }
+                    choose(N-2, s[0]-2) -
+                        if N >= 4 && s[0] >= 4 { choose(N-4, s[0].checked_sub(4).unwrap()) }
                         else { 0 }
                 ))),
             }
 
             match (u, v) {
-                (0, 1) => assert_eq!(slope_0, Some(&-choose(N-2, S[0]-2))),
+                (0, 1) => assert_eq!(slope_0, Some(&-choose(N-2, s[0]-2))),
                 _ => assert_eq!(slope_0, None)
             }
         }
     }
+
+    #[test]
+    fn one_recoloring() {
+        check_one_recoloring([3, 3]);
+        check_one_recoloring([4, 4]);
+        check_one_recoloring([3, 5]);
+        check_one_recoloring([3, 3, 3]);
+    }
 }
 
 #[cfg(test)]
 mod test_random_recoloring {
     use itertools::Itertools;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use crate::{prelude::{choose, choose_two, Iyy, pos_to_edge}, colored_graph::{neighborhood::{U8N, Neighborhood}, ColoredGraph, ColoredEdge}, action_matrix::{ActionMatrix, Action}};
 
-    use crate::{prelude::{choose, choose_two, S, Iyy, pos_to_edge}, colored_graph::{neighborhood::{UxxN, Neighborhood}, ColoredGraph, ColoredEdge}, action_matrix::{ActionMatrix, Action}};
-    
-    const C: usize = 2;
     const N: usize = 8;
     const E: usize = choose_two(N);
 
-    type T = UxxN<N>;
+    type T = U8N;
 
-    #[test]
-    fn consistent_counts() {
-        let mut actions = ActionMatrix::<T, C, N, E>::from(ColoredGraph::<T, C, N>::red());
-        let mut rng = rand::thread_rng();
+    fn check_consistent_counts<const C: usize>(s: [usize; C]) {
+        let mut actions = ActionMatrix::<T, C>::new(ColoredGraph::<T, C>::red(N), s);
+        let mut rng = StdRng::seed_from_u64(1);
         for _ in 0..100 {
-            for c in 0..C {
-                let graph_count = actions.graph.count_cliques(c, None, None);
+            for (c, &s) in s.iter().enumerate() {
+                let graph_count = actions.graph.count_cliques(c, s, None);
                 let matrix_count: Iyy = (0..N)
                     .tuple_combinations()
                     .enumerate()
@@ -96,19 +113,27 @@ mod test_random_recoloring {
                         actions.counts[c][pos]
                     })
                     .sum();
-                assert_eq!(graph_count * choose(S[c], 2), matrix_count);
+                assert_eq!(graph_count * choose(s, 2), matrix_count);
             }
             actions.randomly_act(&mut rng);
         }
     }
 
-    impl<T: Neighborhood> ActionMatrix<T, C, N, E> {
+    #[test]
+    fn consistent_counts() {
+        check_consistent_counts([3, 3]);
+        check_consistent_counts([4, 4]);
+        check_consistent_counts([3, 5]);
+        check_consistent_counts([3, 3, 3]);
+    }
+
+    impl<T: Neighborhood, const C: usize> ActionMatrix<T, C> {
         fn _calculate_slope(&self, (new_color, pos): Action) -> Option<Iyy> {
-            let edge = pos_to_edge::<N>(pos);
+            let edge = pos_to_edge(N, pos);
             let old_color = self.graph.color(edge)
                 .unwrap();
-        
-            if old_color == new_color { None } 
+
+            if old_color == new_color { None }
             else { Some(
                 self.counts[old_color][pos] -
                 self.counts[new_color][pos]
@@ -116,15 +141,14 @@ mod test_random_recoloring {
         }
     }
 
-    #[test]
-    fn verify_all_slopes() {
-        let mut actions: ActionMatrix<T, C, N, E> = ActionMatrix::from(ColoredGraph::red());
-        let mut rng = rand::thread_rng();
+    fn check_all_slopes<const C: usize>(s: [usize; C]) {
+        let mut actions: ActionMatrix<T, C> = ActionMatrix::new(ColoredGraph::red(N), s);
+        let mut rng = StdRng::seed_from_u64(2);
         for _ in 0..100 {
             for c in 0..C {
                 for pos in 0..E {
                     let slope = actions.slope((c, pos))
-                        .map(|&x| x);
+                        .copied();
                     let calculated_slope = actions._calculate_slope((c, pos));
                     assert_eq!(slope, calculated_slope)
                 }
@@ -134,13 +158,89 @@ mod test_random_recoloring {
     }
 
     #[test]
-    fn consistent_scores() {
-        let mut actions = ActionMatrix::<T, C, N, E>::from(ColoredGraph::<T, C, N>::red());
-        let mut rng = rand::thread_rng();
+    fn verify_all_slopes() {
+        check_all_slopes([3, 3]);
+        check_all_slopes([4, 4]);
+        check_all_slopes([3, 5]);
+        check_all_slopes([3, 3, 3]);
+    }
+
+    fn check_consistent_scores<const C: usize>(s: [usize; C]) {
+        let mut actions = ActionMatrix::<T, C>::new(ColoredGraph::<T, C>::red(N), s);
+        let mut rng = StdRng::seed_from_u64(3);
         for _ in 0..100 {
-            assert_eq!(actions.score(), actions.graph.score());
+            assert_eq!(actions.score(), actions.graph.score(&s));
             assert_eq!(actions.score(), actions.total());
             actions.randomly_act(&mut rng)
         }
     }
+
+    #[test]
+    fn consistent_scores() {
+        check_consistent_scores([3, 3]);
+        check_consistent_scores([4, 4]);
+        check_consistent_scores([3, 5]);
+        check_consistent_scores([3, 3, 3]);
+    }
+}
+
+#[cfg(test)]
+mod wide_graphs {
+    use itertools::Itertools;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use crate::{prelude::{choose, choose_two, Iyy, pos_to_edge}, colored_graph::{neighborhood::{U128N, U192N, U256N, Neighborhood}, ColoredGraph, ColoredEdge}, action_matrix::ActionMatrix};
+
+    /* random starts, since the red K_N has too many cliques to recount at these sizes */
+    fn check_consistent_counts<T: Neighborhood, const C: usize>(n: usize, s: [usize; C]) {
+        let mut rng = StdRng::seed_from_u64(4);
+        let mut actions = ActionMatrix::<T, C>::new(ColoredGraph::uniformly_random(n, &mut rng), s);
+        for _ in 0..20 {
+            for (c, &s) in s.iter().enumerate() {
+                let graph_count = actions.graph.count_cliques(c, s, None);
+                let matrix_count: Iyy = (0..n)
+                    .tuple_combinations()
+                    .enumerate()
+                    .filter(|(_, (u, v))| actions.graph.has_edge(ColoredEdge { color: c, edge: (*u, *v) }))
+                    .map(|(pos, _)| actions.counts[c][pos])
+                    .sum();
+                assert_eq!(graph_count * choose(s, 2), matrix_count);
+            }
+            actions.randomly_act(&mut rng);
+        }
+    }
+
+    fn check_all_slopes<T: Neighborhood, const C: usize>(n: usize, s: [usize; C]) {
+        let mut rng = StdRng::seed_from_u64(5);
+        let mut actions = ActionMatrix::<T, C>::new(ColoredGraph::uniformly_random(n, &mut rng), s);
+        for _ in 0..20 {
+            for new_color in 0..C {
+                for pos in 0..choose_two(n) {
+                    let old_color = actions.graph.color(pos_to_edge(n, pos)).unwrap();
+                    let calculated_slope = (old_color != new_color)
+                        .then(|| actions.counts[old_color][pos] - actions.counts[new_color][pos]);
+                    assert_eq!(actions.slope((new_color, pos)).copied(), calculated_slope)
+                }
+            }
+            actions.randomly_act(&mut rng)
+        }
+    }
+
+    #[test]
+    fn consistent_counts() {
+        check_consistent_counts::<U128N, 2>(70, [3, 3]);
+        check_consistent_counts::<U128N, 3>(70, [3, 3, 4]);
+        check_consistent_counts::<U128N, 2>(128, [3, 4]);
+        check_consistent_counts::<U192N, 2>(192, [3, 4]);
+        check_consistent_counts::<U256N, 2>(256, [3, 3]);
+    }
+
+    #[test]
+    fn verify_all_slopes() {
+        check_all_slopes::<U128N, 2>(70, [3, 3]);
+        check_all_slopes::<U128N, 3>(70, [3, 3, 4]);
+        check_all_slopes::<U128N, 2>(128, [3, 4]);
+        check_all_slopes::<U192N, 2>(192, [3, 4]);
+        check_all_slopes::<U256N, 2>(256, [3, 3]);
+    }
 }
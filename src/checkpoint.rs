@@ -0,0 +1,252 @@
+use std::{borrow::Borrow, path::{Path, PathBuf}, time::{Duration, Instant}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::{prelude::*, config::{SearchConfig, ConfigError}};
+use crate::action_matrix::{ActionMatrix, EdgePos};
+use crate::colored_graph::{ColoredGraph, display::Graph6Error, neighborhood::Neighborhood};
+
+/* one action taken from a visited graph, read from a bare tuple in older checkpoints */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct VisitAction {
+    pub color: Color,
+    pub pos: EdgePos,
+    /* the slope when first taken */
+    pub q_ga: Iyy,
+    pub n_ga: Uzz,
+    /* the backed-up value, missing from checkpoints that predate it */
+    #[serde(default)]
+    pub value: Option<f64>
+}
+
+/* the visit counts of one graph in the `GraphMap` */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Visit {
+    pub graph: Vec<String>,
+    pub n_visits: Uzz,
+    pub actions: Vec<VisitAction>,
+    /* the `GraphMap` clock at the first and the latest visit, 0 in checkpoints that predate them */
+    #[serde(default)]
+    pub first_visit: Uzz,
+    #[serde(default)]
+    pub last_visit: Uzz
+}
+
+/* what a strategy needs to pick up where it stopped */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct SearchState {
+    /* the epoch in progress, counting from 1 */
+    pub epoch: usize,
+    /* episodes of `epoch` already played */
+    pub episode: Uzz,
+    pub best_count: Iyy,
+    /* g6 strings of the tied best colorings, one per color */
+    pub roots: Vec<Vec<String>>,
+    #[serde(default)]
+    pub visits: Vec<Visit>
+}
+
+impl SearchState {
+    pub fn new<T: Neighborhood, const C: usize>(
+        epoch: usize,
+        episode: Uzz,
+        roots: &[impl Borrow<ActionMatrix<T, C>>],
+        visits: Vec<Visit>
+    ) -> Self {
+        SearchState {
+            epoch,
+            episode,
+            best_count: roots[0].borrow().total(),
+            roots: roots.iter().map(|root| root.borrow().graph().graph6s()).collect(),
+            visits
+        }
+    }
+
+    pub fn roots<T: Neighborhood, const C: usize>(&self, n: usize, s: [usize; C])
+    -> Result<Vec<ActionMatrix<T, C>>, Graph6Error>
+    {
+        self.roots
+            .iter()
+            .map(|root| ColoredGraph::from_graph6s(root, n).map(|graph| ActionMatrix::new(graph, s)))
+            .collect()
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Checkpoint {
+    pub config: SearchConfig,
+    /* seconds spent searching over every run so far */
+    pub elapsed: f64,
+    #[serde(flatten)]
+    pub state: SearchState
+}
+
+impl Checkpoint {
+    pub fn load(path: &Path) -> Result<Checkpoint, ConfigError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| ConfigError::Read { path: path.to_path_buf(), error: error.to_string() })?;
+        serde_json::from_str(&contents)
+            .map_err(|error| ConfigError::Format { path: path.to_path_buf(), error: error.to_string() })
+    }
+
+    /* written beside `path` first, so a crash mid-write leaves the previous checkpoint intact */
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        std::fs::write(&partial, serde_json::to_string(self)?)?;
+        std::fs::rename(&partial, path)
+    }
+}
+
+/* writes checkpoints at most every `every`, and whenever the search stops */
+pub struct Checkpointer {
+    path: PathBuf,
+    every: Duration,
+    visits: bool,
+    config: SearchConfig,
+    started: Instant,
+    elapsed: f64,
+    last: Instant
+}
+
+impl Checkpointer {
+    /* `elapsed` seconds were already spent by the runs before this one */
+    pub fn new(config: &SearchConfig, elapsed: f64) -> Option<Self> {
+        let path = config.checkpoint.clone()?;
+        let now = Instant::now();
+        Some(Checkpointer {
+            path,
+            every: Duration::from_secs_f64(config.checkpoint_every),
+            visits: config.checkpoint_visits,
+            config: config.clone(),
+            started: now,
+            elapsed,
+            last: now
+        })
+    }
+
+    /* `Some(with_visits)` when a checkpoint should be written now */
+    pub fn due(&self, finished: bool) -> Option<bool> {
+        (finished || self.last.elapsed() >= self.every).then_some(self.visits)
+    }
+
+    /* until `due` next says yes */
+    pub fn wait(&self) -> Duration {
+        self.every.saturating_sub(self.last.elapsed())
+    }
+
+    pub fn save(&mut self, state: SearchState) {
+        let checkpoint = Checkpoint {
+            config: self.config.clone(),
+            elapsed: self.elapsed + self.started.elapsed().as_secs_f64(),
+            state
+        };
+        match checkpoint.save(&self.path) {
+            Ok(()) => println!("\rcheckpoint: epoch {}, episode {} -> {}", checkpoint.state.epoch, checkpoint.state.episode, self.path.display()),
+            Err(error) => eprintln!("could not write {}: {error}", self.path.display())
+        }
+        self.last = Instant::now();
+    }
+}
+
+#[cfg(test)]
+mod checkpoint_tests {
+    use crate::colored_graph::neighborhood::{U8N, U16N, U32N};
+    use crate::learning_loop::Mcts;
+    use crate::strategy::{Budget, Observer, SearchError, SearchStrategy, resume_config, start};
+    use rand::{SeedableRng, rngs::StdRng};
+    use super::*;
+
+    /* asks for a checkpoint with visits after every episode */
+    #[derive(Default)]
+    struct Recorder {
+        epochs: Vec<usize>,
+        states: Vec<SearchState>
+    }
+
+    impl<T: Neighborhood, const C: usize>
+    Observer<T, C> for Recorder {
+        fn epoch(&mut self, epoch: usize) { self.epochs.push(epoch) }
+        fn checkpoint_due(&mut self, _finished: bool) -> Option<bool> { Some(true) }
+        fn next_checkpoint(&self) -> Option<Duration> { Some(Duration::ZERO) }
+        fn checkpoint(&mut self, state: SearchState) { self.states.push(state) }
+    }
+
+    #[test]
+    fn mcts_resumes_where_it_stopped() {
+        type Strategy = Mcts<U32N, 2>;
+        let config = SearchConfig { s: vec![4, 4], n: 17, epochs: 3, episodes: 10, ..Default::default() };
+        let mut recorder = Recorder::default();
+        let mut budget = Budget::new(&config);
+        budget.epochs = 1;
+        let mut rng = StdRng::seed_from_u64(1);
+        let best = Strategy::new(&config).search(start(&config, &mut rng), &budget, &mut recorder, &mut rng).unwrap().best;
+        let state = recorder.states.pop().unwrap();
+        assert_eq!((state.epoch, state.episode, state.best_count), (1, 10, best.total()));
+        assert!(!state.visits.is_empty());
+        assert_eq!(recorder.states.len(), 10);
+
+        let mut resumed = Strategy::new(&config);
+        let roots = state.roots::<U32N, 2>(17, [4, 4]).unwrap();
+        let start = roots[0].clone();
+        resumed.restore(roots, &state.visits).unwrap();
+        let mut recorder = Recorder::default();
+        let budget = Budget::resume(&config, state.epoch, state.episode, 0.0);
+        let best = resumed.search(start, &budget, &mut recorder, &mut rng).unwrap().best;
+        assert!(best.total() <= state.best_count);
+        assert_eq!(recorder.epochs, [1, 2, 3]);
+        let last = recorder.states.pop().unwrap();
+        assert_eq!((last.epoch, last.episode), (3, 10));
+        assert!(last.visits.len() >= state.visits.len());
+    }
+
+    #[test]
+    fn json_round_trip() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let roots: Vec<ActionMatrix<U8N, 3>> = (0..3)
+            .map(|_| ActionMatrix::new(ColoredGraph::uniformly_random(8, &mut rng), [3, 3, 3]))
+            .collect();
+        let visits = vec![Visit { graph: roots[1].graph().graph6s(), n_visits: 5, actions: vec![VisitAction { color: 2, pos: 27, q_ga: -4, n_ga: 3, value: Some(1.5) }], first_visit: 2, last_visit: 9 }];
+        let checkpoint = Checkpoint {
+            config: SearchConfig { s: vec![3, 3, 3], n: 8, ..Default::default() },
+            elapsed: 12.5,
+            state: SearchState::new(4, 321, &roots, visits)
+        };
+
+        let path = std::env::temp_dir().join(format!("checkpoint_{}.json", std::process::id()));
+        checkpoint.save(&path).unwrap();
+        let loaded = Checkpoint::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, checkpoint);
+        assert!(loaded.state.roots::<U8N, 3>(8, [3, 3, 3]).unwrap() == roots);
+        assert!(loaded.state.roots::<U16N, 3>(9, [3, 3, 3]).is_err());
+    }
+
+    #[test]
+    fn visits_read_older_actions() {
+        let visit: Visit = serde_json::from_str(r#"{"graph": [], "n_visits": 5, "actions": [[2, 27, -4, 3]]}"#).unwrap();
+        assert_eq!(visit.actions, [VisitAction { color: 2, pos: 27, q_ga: -4, n_ga: 3, value: None }]);
+        assert_eq!((visit.first_visit, visit.last_visit), (0, 0));
+        /* values kept apart from their actions are refused rather than dropped */
+        assert!(serde_json::from_str::<Visit>(r#"{"graph": [], "n_visits": 5, "actions": [[2, 27, -4, 3]], "values": [1.5]}"#).is_err());
+    }
+
+    #[test]
+    fn resumes_keep_the_problem() {
+        let mut rng = StdRng::seed_from_u64(3);
+        /* R(3,3) = 6, so the search that may go ahead writes no witness */
+        let config = SearchConfig { s: vec![3, 3], n: 6, epochs: 2, episodes: 10, ..Default::default() };
+        let roots = [ActionMatrix::<U8N, 2>::new(ColoredGraph::uniformly_random(6, &mut rng), [3, 3])];
+        let checkpoint = Checkpoint { config: config.clone(), elapsed: 0.0, state: SearchState::new(1, 4, &roots, vec![]) };
+        let resume = |config: SearchConfig| resume_config(&config, &checkpoint).map(|_| ());
+        let changed = |field, saved: &str, given: &str|
+            Err(SearchError::Config(ConfigError::ResumeChanged { field, saved: saved.into(), given: given.into() }));
+
+        assert_eq!(resume(SearchConfig { s: vec![3, 4], ..config.clone() }), changed("S", "[3, 3]", "[3, 4]"));
+        assert_eq!(resume(SearchConfig { n: 7, ..config.clone() }), changed("N", "6", "7"));
+        assert_eq!(resume(SearchConfig { canonical: true, ..config.clone() }), changed("CANONICAL", "false", "true"));
+        /* the budget and runtime knobs may change */
+        assert_eq!(resume(SearchConfig { epochs: 3, threads: 2, time_limit: Some(60.0), ..config }), Ok(()));
+    }
+}
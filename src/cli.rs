@@ -0,0 +1,281 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use mcts_ramsey::checkpoint::Checkpoint;
+use mcts_ramsey::config::{SearchConfig, ConfigError, GuessP, Strategy, Schedule, Proposal, Eviction, Policy, Backup, Length};
+
+#[derive(Parser)]
+#[command(version, about = "Tabular MCTS Ramsey search", args_conflicts_with_subcommands = true)]
+pub struct Cli {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+    #[command(flatten)]
+    pub search: SearchArgs
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Search for a coloring of K_N with no K_S[c] in any color c (the default)
+    Search(Box<SearchArgs>),
+    /// Check that g6 strings, one per color, witness R(S) > N
+    Verify(VerifyArgs),
+    /// Draw g6 strings, one per color, as SVG or TikZ pictures
+    Render(RenderArgs),
+    /// Translate a coloring between the g6 and matrix formats
+    Convert(ConvertArgs),
+    /// Delete the SVG files in the plots directory
+    Clean(CleanArgs)
+}
+
+#[derive(Args)]
+pub struct SearchArgs {
+    /// Experiment file (.toml or .json); flags and environment variables override its entries
+    #[arg(short, long)]
+    pub config: Option<PathBuf>,
+    /// Continue the search saved in a checkpoint, under its config unless flags override it
+    #[arg(long, conflicts_with = "config")]
+    pub resume: Option<PathBuf>,
+    /// Clique sizes, one per color [default: 3,3]
+    #[arg(short, long, env = "S", value_delimiter = ',')]
+    pub s: Option<Vec<usize>>,
+    /// Number of vertices [default: 5]
+    #[arg(short, long, env = "N")]
+    pub n: Option<usize>,
+    /// [default: 50]
+    #[arg(long, env = "EPOCHS")]
+    pub epochs: Option<usize>,
+    /// [default: 10000]
+    #[arg(long, env = "EPISODES")]
+    pub episodes: Option<u64>,
+    /// Maximum number of tied minima kept as roots [default: 250]
+    #[arg(long, env = "ROOTS")]
+    pub roots: Option<usize>,
+    /// Exploration constant [default: 4.5]
+    #[arg(long, env = "EXPLORE")]
+    pub explore: Option<f64>,
+    /// Merge colorings that differ by a relabeling of the vertices in the MCTS statistics and tied roots
+    #[arg(long)]
+    pub canonical: bool,
+    /// Likewise merge colorings that differ by swapping colors with equal clique sizes
+    #[arg(long)]
+    pub permute_colors: bool,
+    /// Initial color weights: "uniform", "auto" or one weight per color, e.g. 0.3,0.7 [default: uniform]
+    #[arg(long, env = "GUESS_P")]
+    pub guess_p: Option<GuessP>,
+    /// Seed for a repeatable single-threaded search, recorded with the results [default: random]
+    #[arg(long)]
+    pub seed: Option<u64>,
+    /// Stop searching after this many seconds
+    #[arg(long)]
+    pub time_limit: Option<f64>,
+    /// Directory for the SVG witnesses [default: plots]
+    #[arg(long)]
+    pub output_dir: Option<PathBuf>,
+    /// Worker threads for MCTS episodes [default: 1]
+    #[arg(short, long, env = "THREADS")]
+    pub threads: Option<usize>,
+    /// Save the search to this file periodically and when it stops
+    #[arg(long)]
+    pub checkpoint: Option<PathBuf>,
+    /// Seconds between checkpoints [default: 300]
+    #[arg(long)]
+    pub checkpoint_every: Option<f64>,
+    /// Also save the MCTS visit counts with each checkpoint
+    #[arg(long)]
+    pub checkpoint_visits: bool,
+    /// Most graphs kept in the MCTS visit counts
+    #[arg(long)]
+    pub max_graphs: Option<usize>,
+    /// Megabytes the MCTS visit counts may take, estimated
+    #[arg(long)]
+    pub max_memory: Option<f64>,
+    /// Graphs evicted first over a cap: visits (least visited), lru or age [default: visits]
+    #[arg(long)]
+    pub eviction: Option<Eviction>,
+    /// MCTS: action selection formula, uct, ucb1 or puct [default: uct]
+    #[arg(long)]
+    pub policy: Option<Policy>,
+    /// MCTS: factor applied to the exploration constant every epoch [default: 1]
+    #[arg(long)]
+    pub explore_decay: Option<f64>,
+    /// MCTS: sample actions by a softmax of their scores at this temperature, 0 to take the best [default: 0]
+    #[arg(long)]
+    pub softmax_temperature: Option<f64>,
+    /// MCTS: temperature of the softmax of slopes that gives puct its priors [default: 1]
+    #[arg(long)]
+    pub prior_temperature: Option<f64>,
+    /// MCTS: value of a taken action, its slope or the mean, max or discounted return of later episodes [default: slope]
+    #[arg(long)]
+    pub backup: Option<Backup>,
+    /// MCTS: discount per move of discounted returns [default: 0.9]
+    #[arg(long)]
+    pub discount: Option<f64>,
+    /// MCTS: episode length schedule, linear, constant, geometric, luby or adaptive [default: linear]
+    #[arg(long)]
+    pub length: Option<Length>,
+    /// MCTS: moves per episode the schedule starts from [default: E/4]
+    #[arg(long)]
+    pub moves: Option<usize>,
+    /// MCTS: moves added every epoch by the linear schedule [default: 1]
+    #[arg(long)]
+    pub length_increment: Option<usize>,
+    /// MCTS: factor per epoch of the geometric schedule [default: 1.1]
+    #[arg(long)]
+    pub length_ratio: Option<f64>,
+    /// MCTS: multiple of the depth of the latest improvement for the adaptive schedule [default: 2]
+    #[arg(long)]
+    pub length_slack: Option<f64>,
+    /// MCTS: most moves per episode under any schedule
+    #[arg(long)]
+    pub max_moves: Option<usize>,
+    /// Search algorithm: mcts, tabu or annealing [default: mcts]
+    #[arg(long, env = "STRATEGY")]
+    pub strategy: Option<Strategy>,
+    /// Tabu search: moves before a recolored edge may change again [default: 20]
+    #[arg(long)]
+    pub tenure: Option<usize>,
+    /// Tabu search: never take tabu moves, even ones that beat the best score
+    #[arg(long)]
+    pub no_aspiration: bool,
+    /// Annealing: temperature at the start of each epoch [default: 2]
+    #[arg(long)]
+    pub temperature: Option<f64>,
+    /// Annealing: temperature at the end of each epoch [default: 0.05]
+    #[arg(long)]
+    pub final_temperature: Option<f64>,
+    /// Annealing: geometric, linear or adaptive cooling [default: geometric]
+    #[arg(long)]
+    pub schedule: Option<Schedule>,
+    /// Annealing: steps without a new best before an adaptive reheat [default: 2000]
+    #[arg(long)]
+    pub patience: Option<u64>,
+    /// Annealing: uniform or slope-biased recolorings [default: uniform]
+    #[arg(long)]
+    pub proposal: Option<Proposal>,
+    /// Annealing: draws per slope-biased proposal [default: 4]
+    #[arg(long)]
+    pub tournament: Option<usize>,
+    /// Start without the confirmation prompt (automatic when stdin is not a terminal)
+    #[arg(short, long)]
+    pub yes: bool
+}
+
+impl SearchArgs {
+    pub fn resumed(&self) -> Result<Option<Checkpoint>, ConfigError> {
+        self.resume
+            .as_deref()
+            .map(Checkpoint::load)
+            .transpose()
+    }
+
+    pub fn config(&self, resumed: Option<&Checkpoint>) -> Result<SearchConfig, ConfigError> {
+        let mut config = match (&self.config, resumed) {
+            (_, Some(checkpoint)) => checkpoint.config.clone(),
+            (Some(path), None) => SearchConfig::load(path)?,
+            (None, None) => SearchConfig::default()
+        };
+        if let Some(s) = &self.s { config.s = s.clone() }
+        if let Some(n) = self.n { config.n = n }
+        if let Some(epochs) = self.epochs { config.epochs = epochs }
+        if let Some(episodes) = self.episodes { config.episodes = episodes }
+        if let Some(roots) = self.roots { config.roots = roots }
+        if let Some(explore) = self.explore { config.explore = explore }
+        if self.canonical { config.canonical = true }
+        if self.permute_colors { config.permute_colors = true }
+        if let Some(guess_p) = &self.guess_p { config.guess_p = guess_p.clone() }
+        if let Some(seed) = self.seed { config.seed = Some(seed) }
+        if let Some(time_limit) = self.time_limit { config.time_limit = Some(time_limit) }
+        if let Some(output_dir) = &self.output_dir { config.output_dir = output_dir.clone() }
+        if let Some(threads) = self.threads { config.threads = threads }
+        if let Some(checkpoint) = &self.checkpoint { config.checkpoint = Some(checkpoint.clone()) }
+        if let Some(every) = self.checkpoint_every { config.checkpoint_every = every }
+        if self.checkpoint_visits { config.checkpoint_visits = true }
+        if let Some(max_graphs) = self.max_graphs { config.memory.max_graphs = Some(max_graphs) }
+        if let Some(megabytes) = self.max_memory { config.memory.max_megabytes = Some(megabytes) }
+        if let Some(eviction) = self.eviction { config.memory.eviction = eviction }
+        if let Some(policy) = self.policy { config.selection.policy = policy }
+        if let Some(decay) = self.explore_decay { config.selection.explore_decay = decay }
+        if let Some(temperature) = self.softmax_temperature { config.selection.softmax_temperature = temperature }
+        if let Some(temperature) = self.prior_temperature { config.selection.prior_temperature = temperature }
+        if let Some(backup) = self.backup { config.selection.backup = backup }
+        if let Some(discount) = self.discount { config.selection.discount = discount }
+        if let Some(length) = self.length { config.length.schedule = length }
+        if let Some(moves) = self.moves { config.length.moves = Some(moves) }
+        if let Some(increment) = self.length_increment { config.length.increment = increment }
+        if let Some(ratio) = self.length_ratio { config.length.ratio = ratio }
+        if let Some(slack) = self.length_slack { config.length.slack = slack }
+        if let Some(max_moves) = self.max_moves { config.length.max_moves = Some(max_moves) }
+        if let Some(strategy) = self.strategy { config.strategy = strategy }
+        if let Some(tenure) = self.tenure { config.tabu.tenure = tenure }
+        if self.no_aspiration { config.tabu.aspiration = false }
+        if let Some(temperature) = self.temperature { config.annealing.temperature = temperature }
+        if let Some(temperature) = self.final_temperature { config.annealing.final_temperature = temperature }
+        if let Some(schedule) = self.schedule { config.annealing.schedule = schedule }
+        if let Some(patience) = self.patience { config.annealing.patience = patience }
+        if let Some(proposal) = self.proposal { config.annealing.proposal = proposal }
+        if let Some(tournament) = self.tournament { config.annealing.tournament = tournament }
+        Ok(config)
+    }
+}
+
+#[derive(Args)]
+pub struct VerifyArgs {
+    /// Clique sizes, one per color
+    #[arg(short, long, value_delimiter = ',', required = true)]
+    pub s: Vec<usize>,
+    /// Number of monochromatic cliques to list when the coloring fails
+    #[arg(long, default_value_t = 10)]
+    pub max_cliques: usize,
+    /// g6 strings, one per color
+    #[arg(required = true)]
+    pub graphs: Vec<String>
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+pub enum PictureFormat {
+    Svg,
+    Tikz
+}
+
+#[derive(Args)]
+pub struct RenderArgs {
+    #[arg(short, long, value_enum, default_value_t = PictureFormat::Svg)]
+    pub format: PictureFormat,
+    /// Files are written to <DIR>/<NAME>_<color>.svg or .tex
+    #[arg(long, default_value = "render")]
+    pub name: String,
+    /// Directory for the pictures, created if missing
+    #[arg(long, default_value = "plots")]
+    pub dir: PathBuf,
+    /// g6 strings, one per color
+    #[arg(required = true)]
+    pub graphs: Vec<String>
+}
+
+#[derive(Clone, Copy, PartialEq, ValueEnum)]
+pub enum GraphFormat {
+    /// One g6 string per color
+    G6,
+    /// Colors of the edges in colex order, e.g. "0; 10; 110; 0110;"
+    Matrix
+}
+
+#[derive(Args)]
+pub struct ConvertArgs {
+    #[arg(long, value_enum)]
+    pub from: GraphFormat,
+    #[arg(long, value_enum)]
+    pub to: GraphFormat,
+    /// Number of colors of a matrix input [default: largest color + 1]
+    #[arg(short, long)]
+    pub colors: Option<usize>,
+    /// g6 strings, one per color, or a single matrix
+    #[arg(required = true)]
+    pub graphs: Vec<String>
+}
+
+#[derive(Args)]
+pub struct CleanArgs {
+    #[arg(long, default_value = "plots")]
+    pub dir: PathBuf
+}
@@ -0,0 +1,395 @@
+use itertools::Itertools;
+
+use crate::prelude::*;
+use super::{ColoredGraph, neighborhood::Neighborhood};
+
+/*
+ * renames vertices and colors, `labels[v]` being the new name of v and `colors[c]` that of c;
+ * empty labels or colors leave them as they are
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Relabeling {
+    labels: Vec<Vertex>,
+    inverse: Vec<Vertex>,
+    colors: Vec<Color>,
+    inverse_colors: Vec<Color>
+}
+
+fn invert(permutation: &[usize]) -> Vec<usize> {
+    let mut inverse = vec![0; permutation.len()];
+    for (i, &image) in permutation.iter().enumerate() {
+        inverse[image] = i
+    }
+    inverse
+}
+
+impl Relabeling {
+    pub fn identity() -> Self {
+        Relabeling::default()
+    }
+
+    pub fn new(labels: Vec<Vertex>) -> Self {
+        Relabeling { inverse: invert(&labels), labels, ..Default::default() }
+    }
+
+    pub fn with_colors(self, colors: Vec<Color>) -> Self {
+        Relabeling { inverse_colors: invert(&colors), colors, ..self }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        self.labels.iter().enumerate().all(|(v, &label)| v == label)
+            && self.colors.iter().enumerate().all(|(c, &color)| c == color)
+    }
+
+    pub fn vertex(&self, v: Vertex) -> Vertex {
+        self.labels.get(v).copied().unwrap_or(v)
+    }
+
+    pub fn color(&self, c: Color) -> Color {
+        self.colors.get(c).copied().unwrap_or(c)
+    }
+
+    pub fn color_back(&self, c: Color) -> Color {
+        self.inverse_colors.get(c).copied().unwrap_or(c)
+    }
+
+    pub fn edge(&self, (u, v): Edge) -> Edge {
+        let (u, v) = (self.vertex(u), self.vertex(v));
+        if u < v { (u, v) } else { (v, u) }
+    }
+
+    /* the edge named `(u, v)` after relabeling */
+    pub fn edge_back(&self, (u, v): Edge) -> Edge {
+        let back = |v: Vertex| self.inverse.get(v).copied().unwrap_or(v);
+        let (u, v) = (back(u), back(v));
+        if u < v { (u, v) } else { (v, u) }
+    }
+}
+
+/* which relabelings count as the same coloring */
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Symmetry {
+    pub vertices: bool,
+    /* the color permutations, identity first */
+    pub colors: Vec<Vec<Color>>
+}
+
+impl Symmetry {
+    /* with `permute_colors`, every permutation of the colors that keeps `s` */
+    pub fn new(vertices: bool, s: &[usize], permute_colors: bool) -> Self {
+        let colors = if permute_colors {
+            (0..s.len())
+                .permutations(s.len())
+                .filter(|colors| colors.iter().enumerate().all(|(c, &image)| s[c] == s[image]))
+                .collect()
+        }
+        else { vec![] };
+        Symmetry { vertices, colors }
+    }
+
+    pub fn is_trivial(&self) -> bool {
+        !self.vertices && self.colors.len() <= 1
+    }
+
+    /* the representative of `graph`'s class, and the relabeling that takes `graph` to it */
+    pub fn canonical<T: Neighborhood, const C: usize>(&self, graph: &ColoredGraph<T, C>)
+    -> (ColoredGraph<T, C>, Relabeling)
+    {
+        let identity = vec![(0..C).collect()];
+        let colors = if self.colors.is_empty() { &identity } else { &self.colors };
+        colors.iter()
+            .map(|colors| {
+                let recolored = graph.permute_colors(colors);
+                let relabeling = match self.vertices {
+                    true => Relabeling::new(recolored.canonical_labeling()),
+                    false => Relabeling::identity()
+                };
+                (recolored.relabel(&relabeling), relabeling.with_colors(colors.clone()))
+            })
+            .min_by_key(|(candidate, _)| candidate.edge_colors())
+            .unwrap()
+    }
+}
+
+type Cells = Vec<Vec<Vertex>>;
+
+/*
+ * individualization-refinement: refine the partition of the vertices until it is equitable,
+ * then branch on each vertex of the first nontrivial cell, and keep the discrete leaf whose
+ * relabeled graph has the least certificate; automorphisms found as equal leaves prune the tree
+ */
+struct Labeler<'a, T: Neighborhood, const C: usize> {
+    graph: &'a ColoredGraph<T, C>,
+    first: Option<(Vec<Color>, Vec<Vertex>)>,
+    best: Option<(Vec<Color>, Vec<Vertex>)>,
+    automorphisms: Vec<Vec<Vertex>>
+}
+
+impl<'a, T: Neighborhood, const C: usize>
+Labeler<'a, T, C> {
+    fn new(graph: &'a ColoredGraph<T, C>) -> Self {
+        Labeler { graph, first: None, best: None, automorphisms: vec![] }
+    }
+
+    fn set(cell: &[Vertex]) -> T {
+        let mut set = T::default();
+        for &v in cell { set.add(v) }
+        set
+    }
+
+    /* counts of `v`'s neighbors in `set`, one per color */
+    fn degrees(&self, v: Vertex, set: T) -> [u32; C] {
+        std::array::from_fn(|c| (self.graph.bit_neighborhood(c, v) & set).n_elements())
+    }
+
+    /* splits cells by their degrees into earlier cells until every split is stable */
+    fn refine(&self, cells: &mut Cells) {
+        let mut splitter = 0;
+        while splitter < cells.len() {
+            let set = Self::set(&cells[splitter]);
+            let mut split = false;
+            let mut i = 0;
+            while i < cells.len() {
+                if cells[i].len() == 1 { i += 1; continue }
+                let mut keyed: Vec<([u32; C], Vertex)> = cells[i].iter()
+                    .map(|&v| (self.degrees(v, set), v))
+                    .collect();
+                keyed.sort_unstable();
+                let parts: Cells = keyed
+                    .chunk_by(|(a, _), (b, _)| a == b)
+                    .map(|part| part.iter().map(|&(_, v)| v).collect())
+                    .collect();
+                let n_parts = parts.len();
+                if n_parts > 1 { split = true }
+                cells.splice(i..=i, parts);
+                i += n_parts
+            }
+            splitter = if split { 0 } else { splitter + 1 }
+        }
+    }
+
+    /* every pair of cells, and every cell with itself, joined in a single color */
+    fn uniform(&self, cells: &Cells) -> bool {
+        let sets: Vec<T> = cells.iter().map(|cell| Self::set(cell)).collect();
+        cells.iter().enumerate().all(|(i, x)| sets.iter().enumerate().all(|(j, &set)| {
+            let edges = (cells[j].len() - usize::from(i == j)) as u32;
+            edges == 0 || self.degrees(x[0], set).contains(&edges)
+        }))
+    }
+
+    /* the colors of the edges in colex order after naming `order[i]` i */
+    fn certificate(&self, order: &[Vertex]) -> Vec<Color> {
+        (0..self.graph.n()).tuple_combinations()
+            .map(|(i, j)| self.graph.color((order[i], order[j])).unwrap())
+            .collect()
+    }
+
+    fn leaf(&mut self, order: Vec<Vertex>) {
+        let certificate = self.certificate(&order);
+        for (known, known_order) in self.first.iter().chain(&self.best) {
+            if *known == certificate {
+                let mut automorphism = vec![0; self.graph.n()];
+                for (&u, &v) in order.iter().zip(known_order) {
+                    automorphism[u] = v
+                }
+                self.automorphisms.push(automorphism);
+                return
+            }
+        }
+        if self.first.is_none() { self.first = Some((certificate.clone(), order.clone())) }
+        if self.best.as_ref().is_none_or(|(best, _)| certificate < *best) {
+            self.best = Some((certificate, order))
+        }
+    }
+
+    /* whether an automorphism fixing `path` takes u to v */
+    fn same_orbit(&self, path: &[Vertex], u: Vertex, v: Vertex) -> bool {
+        let mut parent: Vec<Vertex> = (0..self.graph.n()).collect();
+        fn root(parent: &mut [Vertex], mut v: Vertex) -> Vertex {
+            while parent[v] != v {
+                parent[v] = parent[parent[v]];
+                v = parent[v]
+            }
+            v
+        }
+        for automorphism in &self.automorphisms {
+            if path.iter().any(|&p| automorphism[p] != p) { continue }
+            for (w, &image) in automorphism.iter().enumerate() {
+                let (a, b) = (root(&mut parent, w), root(&mut parent, image));
+                parent[a] = b
+            }
+        }
+        root(&mut parent, u) == root(&mut parent, v)
+    }
+
+    fn search(&mut self, mut cells: Cells, path: &mut Vec<Vertex>) {
+        self.refine(&mut cells);
+        if cells.len() == self.graph.n() || self.uniform(&cells) {
+            return self.leaf(cells.concat())
+        }
+        let target = cells.iter().position(|cell| cell.len() > 1).unwrap();
+        let mut tried: Vec<Vertex> = vec![];
+        for &v in &cells[target] {
+            if tried.iter().any(|&u| self.same_orbit(path, u, v)) { continue }
+            tried.push(v);
+            let rest: Vec<Vertex> = cells[target].iter().copied().filter(|&u| u != v).collect();
+            let mut child = cells.clone();
+            child.splice(target..=target, [vec![v], rest]);
+            path.push(v);
+            self.search(child, path);
+            path.pop();
+        }
+    }
+}
+
+impl<T: Neighborhood, const C: usize>
+ColoredGraph<T, C> {
+    /* `labels[v]` is v's name in the canonical form, equal for isomorphic colorings */
+    pub fn canonical_labeling(&self) -> Vec<Vertex> {
+        let mut labeler = Labeler::new(self);
+        labeler.search(vec![(0..self.n()).collect()], &mut vec![]);
+        let (_, order) = labeler.best.unwrap();
+        let mut labels = vec![0; self.n()];
+        for (i, v) in order.into_iter().enumerate() {
+            labels[v] = i
+        }
+        labels
+    }
+
+    pub fn canonical(&self) -> ColoredGraph<T, C> {
+        self.relabel(&Relabeling::new(self.canonical_labeling()))
+    }
+
+    /* renames the vertices and the colors */
+    pub fn relabel(&self, relabeling: &Relabeling) -> ColoredGraph<T, C> {
+        let mut relabeled = ColoredGraph::<T, C>::empty(self.n());
+        for (u, neighborhoods) in self.neighborhoods.iter().enumerate() {
+            for (c, neighborhood) in neighborhoods.iter().enumerate() {
+                for v in neighborhood.iter() {
+                    relabeled.neighborhoods[relabeling.vertex(u)][relabeling.color(c)].add(relabeling.vertex(v))
+                }
+            }
+        }
+        relabeled
+    }
+
+    /* color c becomes `colors[c]` */
+    pub fn permute_colors(&self, colors: &[Color]) -> ColoredGraph<T, C> {
+        let mut permuted = self.clone();
+        for (neighborhoods, permuted) in self.neighborhoods.iter().zip(&mut permuted.neighborhoods) {
+            for (c, &image) in colors.iter().enumerate() {
+                permuted[image] = neighborhoods[c]
+            }
+        }
+        permuted
+    }
+
+    /* the color of each edge in colex order, which orders colorings */
+    pub fn edge_colors(&self) -> Vec<Color> {
+        (0..self.n()).tuple_combinations()
+            .map(|edge| self.color(edge).unwrap())
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod canonical_tests {
+    use rand::seq::SliceRandom;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use crate::colored_graph::{Recoloring, neighborhood::{U8N, U32N, U64N}};
+    use super::*;
+
+    fn shuffled<T: Neighborhood, const C: usize>(graph: &ColoredGraph<T, C>, rng: &mut StdRng) -> ColoredGraph<T, C> {
+        let mut labels: Vec<Vertex> = (0..graph.n()).collect();
+        labels.shuffle(rng);
+        graph.relabel(&Relabeling::new(labels))
+    }
+
+    fn check_invariant<T: Neighborhood, const C: usize>(graph: &ColoredGraph<T, C>, rng: &mut StdRng) {
+        let canonical = graph.canonical();
+        for _ in 0..5 {
+            assert!(shuffled(graph, rng).canonical() == canonical)
+        }
+        assert!(canonical.canonical() == canonical);
+    }
+
+    #[test]
+    fn relabelings_agree() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..10 {
+            let graph = ColoredGraph::<U32N, 2>::uniformly_random(20, &mut rng);
+            check_invariant(&graph, &mut rng);
+            let graph = ColoredGraph::<U8N, 3>::uniformly_random(8, &mut rng);
+            check_invariant(&graph, &mut rng);
+        }
+        check_invariant(&ColoredGraph::<U64N, 2>::red(64), &mut rng);
+    }
+
+    #[test]
+    fn symmetric_colorings() {
+        let mut rng = StdRng::seed_from_u64(2);
+        /* the Paley graph on 17 vertices, red on the quadratic residues */
+        let mut paley = ColoredGraph::<U32N, 2>::red(17);
+        for (u, v) in (0..17).tuple_combinations() {
+            if ![1, 2, 4, 8, 9, 13, 15, 16].contains(&((v - u) % 17)) {
+                paley.recolor(Recoloring { old_color: 0, new_color: 1, edge: (u, v) })
+            }
+        }
+        check_invariant(&paley, &mut rng);
+
+        /* ten red triangles */
+        let mut triangles = ColoredGraph::<U32N, 2>::red(30);
+        for (u, v) in (0..30).tuple_combinations() {
+            if u / 3 != v / 3 {
+                triangles.recolor(Recoloring { old_color: 0, new_color: 1, edge: (u, v) })
+            }
+        }
+        check_invariant(&triangles, &mut rng);
+    }
+
+    #[test]
+    fn distinguishes_colorings() {
+        let mut path = ColoredGraph::<U8N, 2>::red(5);
+        let mut star = path.clone();
+        for edge in [(0, 1), (1, 2), (2, 3), (3, 4)] {
+            path.recolor(Recoloring { old_color: 0, new_color: 1, edge })
+        }
+        for edge in [(0, 1), (0, 2), (0, 3), (0, 4)] {
+            star.recolor(Recoloring { old_color: 0, new_color: 1, edge })
+        }
+        assert!(path.canonical() != star.canonical());
+        assert!(path.canonical() == shuffled(&path, &mut StdRng::seed_from_u64(3)).canonical());
+    }
+
+    #[test]
+    fn relabelings_invert() {
+        let relabeling = Relabeling::new(vec![2, 0, 3, 1]);
+        for edge in (0..4).tuple_combinations() {
+            assert_eq!(relabeling.edge_back(relabeling.edge(edge)), edge)
+        }
+        assert_eq!(Relabeling::identity().edge((3, 1)), (1, 3));
+    }
+
+    #[test]
+    fn color_permutations() {
+        assert_eq!(Symmetry::new(false, &[3, 3, 3], true).colors.len(), 6);
+        assert_eq!(Symmetry::new(false, &[3, 4, 3], true).colors, [vec![0, 1, 2], vec![2, 1, 0]]);
+        assert!(Symmetry::new(false, &[3, 4], true).is_trivial());
+        assert!(!Symmetry::new(true, &[3, 4], false).is_trivial());
+
+        let mut rng = StdRng::seed_from_u64(4);
+        let graph = ColoredGraph::<U32N, 3>::uniformly_random(12, &mut rng);
+        let swapped = graph.permute_colors(&[2, 1, 0]);
+        for vertices in [false, true] {
+            let symmetry = Symmetry::new(vertices, &[3, 4, 3], true);
+            let (key, relabeling) = symmetry.canonical(&graph);
+            assert!(graph.relabel(&relabeling) == key);
+            assert!(symmetry.canonical(&swapped).0 == key);
+            /* only colors with equal clique sizes trade places */
+            assert!(symmetry.canonical(&graph.permute_colors(&[1, 0, 2])).0 != key);
+        }
+        let both = Symmetry::new(true, &[3, 4, 3], true);
+        assert!(both.canonical(&shuffled(&swapped, &mut rng)).0 == both.canonical(&graph).0);
+    }
+}
@@ -0,0 +1,132 @@
+use crate::prelude::*;
+use super::{Clique, ColoredGraph, neighborhood::Neighborhood};
+
+/* depth-first over the same candidates as `count_cliques`, one open iterator per level */
+pub struct Cliques<'a, T: Neighborhood, const C: usize> {
+    graph: &'a ColoredGraph<T, C>,
+    color: Color,
+    s: usize,
+    prefix: Vec<Vertex>,
+    clique: Vec<Vertex>,
+    candidates: Vec<T>,
+    levels: Vec<T::Iter>,
+    empty_clique: bool
+}
+
+impl<'a, T: Neighborhood, const C: usize>
+Cliques<'a, T, C> {
+    fn new(graph: &'a ColoredGraph<T, C>, color: Color, s: usize, prefix: Vec<Vertex>, candidates: T) -> Self {
+        let (candidates, levels) = if s == 0 { (vec![], vec![]) } else { (vec![candidates], vec![candidates.iter()]) };
+        Cliques { graph, color, s, prefix, clique: vec![], candidates, levels, empty_clique: s == 0 }
+    }
+
+    fn clique(&self, u: Vertex) -> Clique {
+        let mut vertices: Vec<Vertex> = self.prefix.iter()
+            .chain(&self.clique)
+            .copied()
+            .chain([u])
+            .collect();
+        vertices.sort_unstable();
+        Clique { color: self.color, vertices }
+    }
+}
+
+impl<T: Neighborhood, const C: usize>
+Iterator for Cliques<'_, T, C> {
+    type Item = Clique;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.empty_clique {
+            self.empty_clique = false;
+            return Some(Clique { color: self.color, vertices: self.prefix.clone() })
+        }
+        loop {
+            let depth = self.levels.len();
+            let u = match self.levels.last_mut()?.next() {
+                Some(u) => u,
+                None => {
+                    self.levels.pop();
+                    self.candidates.pop();
+                    self.clique.pop();
+                    continue
+                }
+            };
+            if depth == self.s { return Some(self.clique(u)) }
+            let candidates = self.candidates[depth - 1]
+                & self.graph.bit_neighborhood(self.color, u)
+                & T::interval_to_end(u);
+            self.clique.push(u);
+            self.candidates.push(candidates);
+            self.levels.push(candidates.iter());
+        }
+    }
+}
+
+impl<T: Neighborhood, const C: usize>
+ColoredGraph<T, C> {
+    /* the K_s in `color` among `candidates`, each once, as `count_cliques` counts them */
+    pub fn cliques(&self, color: Color, s: usize, candidates: Option<T>) -> Cliques<'_, T, C> {
+        Cliques::new(self, color, s, vec![], candidates.unwrap_or(T::full(self.n())))
+    }
+
+    /* the K_s in `color` through the edge uv, as `count_edge_cliques` counts them; none for s < 2 */
+    pub fn edge_cliques(&self, color: Color, s: usize, (u, v): Edge) -> Cliques<'_, T, C> {
+        match s.checked_sub(2) {
+            Some(s) => Cliques::new(self, color, s, vec![u, v], self.common_neighborhood(color, u, v)),
+            /* one vertex to go, from no candidates */
+            None => Cliques::new(self, color, 1, vec![u, v], T::default())
+        }
+    }
+}
+
+#[cfg(test)]
+mod clique_tests {
+    use itertools::Itertools;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use crate::colored_graph::{ColoredGraph, neighborhood::{U8N, U16N}};
+    use super::*;
+
+    #[test]
+    fn red_cliques() {
+        let red = ColoredGraph::<U8N, 2>::red(6);
+        let cliques: Vec<Vec<Vertex>> = red.cliques(0, 3, None)
+            .map(|clique| clique.vertices)
+            .collect();
+        assert_eq!(cliques, (0..6).combinations(3).collect::<Vec<_>>());
+        assert_eq!(red.cliques(1, 3, None).count(), 0);
+        assert_eq!(red.cliques(1, 0, None).count(), 1);
+        assert_eq!(red.edge_cliques(0, 4, (2, 4)).count() as Iyy, choose(4, 2));
+        assert!(red.edge_cliques(0, 4, (2, 4)).all(|clique| clique.vertices.contains(&2) && clique.vertices.contains(&4)));
+        assert_eq!(red.edge_cliques(0, 2, (2, 4)).map(|clique| clique.vertices).collect::<Vec<_>>(), [vec![2, 4]]);
+        assert_eq!(red.edge_cliques(0, 1, (2, 4)).count(), 0);
+        assert_eq!(red.edge_cliques(0, 0, (2, 4)).count(), 0);
+    }
+
+    #[test]
+    fn cliques_match_counts() {
+        const N: usize = 12;
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..20 {
+            let graph = ColoredGraph::<U16N, 3>::uniformly_random(N, &mut rng);
+            for (color, s) in (0..3).cartesian_product(1..6) {
+                let cliques: Vec<Clique> = graph.cliques(color, s, None).collect();
+                assert_eq!(cliques.len() as Iyy, graph.count_cliques(color, s, None));
+                assert!(cliques.iter().all(|clique| clique.vertices.len() == s && clique.vertices.is_sorted()));
+                assert!(cliques.iter().map(|clique| &clique.vertices).all_unique());
+                assert!(cliques.iter().all(|clique| clique.vertices.iter()
+                    .tuple_combinations()
+                    .all(|(&u, &v)| graph.color((u, v)) == Some(color))));
+                if s < 2 { continue }
+                for (u, v) in (0..N).tuple_combinations().filter(|&edge| graph.color(edge) == Some(color)) {
+                    let through_uv = graph.edge_cliques(color, s, (u, v)).collect::<Vec<_>>();
+                    assert_eq!(through_uv.len() as Iyy, graph.count_edge_cliques(color, s, (u, v)));
+                    assert_eq!(through_uv, cliques.iter()
+                        .filter(|clique| clique.vertices.contains(&u) && clique.vertices.contains(&v))
+                        .cloned()
+                        .collect::<Vec<_>>());
+                }
+            }
+        }
+    }
+}
@@ -3,21 +3,48 @@ use crate::{prelude::*, colored_graph::neighborhood::*};
 use bit_fiddler::is_set;
 use itertools::Itertools;
 
-impl<T: Neighborhood, const C: usize, const N: usize>
-ColoredGraph<T, C, N> {
+impl<T: Neighborhood, const C: usize>
+ColoredGraph<T, C> {
     pub fn show_matrix(&self) {
         if C == 0 { println!("Colorless graph!") }
-        for u in 1..N {
+        print!("{}", self.matrix());
+    }
+
+    pub fn matrix(&self) -> String {
+        let mut matrix = String::new();
+        for u in 1..self.n() {
             for v in 0..u {
                 let c = self.color((u, v)).unwrap_or(C);
-                print!("{c}");
+                matrix.push_str(&c.to_string());
+            }
+            matrix.push_str("; ");
+        }
+        matrix
+    }
+
+    pub fn from_matrix(matrix: &str, n: usize) -> Result<Self, String> {
+        let rows: Vec<&str> = matrix
+            .split(';')
+            .map(str::trim)
+            .filter(|row| !row.is_empty())
+            .collect();
+        if rows.len() + 1 != n { return Err(format!("{} rows != {n} - 1", rows.len())) }
+        let mut graph = ColoredGraph::empty(n);
+        for (u, row) in (1..n).zip(rows) {
+            if row.len() != u { return Err(format!("row {u} has {} entries", row.len())) }
+            for (v, c) in row.chars().enumerate() {
+                let c = c.to_digit(10)
+                    .map(|c| c as Color)
+                    .filter(|&c| c < C)
+                    .ok_or(format!("{c:?} is not a color below {C}"))?;
+                graph.add(c, (u, v));
             }
-            print!("; ");
         }
+        Ok ( graph )
     }
 
     pub fn show_neighborhoods(&self) {
-        for u in 0..N {
+        for u in 0..self.n() {
             print!("vertex {u}:");
             for c in 0..C {
                 print!(" {:?}", self.bit_neighborhood(c, u).iter().collect::<Vec<_>>());
@@ -27,14 +54,10 @@ ColoredGraph<T, C, N> {
     }
 
     pub fn graph6(&self, color: Color) -> String {
-        let mut graph = match N.cmp(&62) {
-            std::cmp::Ordering::Less | 
-            std::cmp::Ordering::Equal => String::from((N+63) as u8 as char),
-            std::cmp::Ordering::Greater => todo!("large N g6 prefix")
-        };
+        let mut graph = graph6_header(self.n());
 
         let (mut word, mut pos): (u8, usize) = (0, 5);
-        for (u, v) in (0..N).flat_map(|v| (0..v).map(move |u| (u, v))) {
+        for (u, v) in (0..self.n()).flat_map(|v| (0..v).map(move |u| (u, v))) {
             let colored_edge = ColoredEdge {color, edge: (v, u)};
             if self.has_edge(colored_edge) {
                 word = bit_fiddler::set!(word, u8, pos);
@@ -63,14 +86,14 @@ ColoredGraph<T, C, N> {
 
 #[cfg(test)]
 mod g6_tests {
-    use crate::colored_graph::{ColoredGraph, Recoloring, neighborhood::UxxN};
+    use crate::colored_graph::{ColoredGraph, Recoloring, neighborhood::U8N};
     const C: usize = 2;
     const N: usize = 5;
     
-    type T = UxxN<N>;
+    type T = U8N;
     #[test]
     fn mckay_example() {
-        let mut graph = ColoredGraph::<T, C, N>::red();
+        let mut graph = ColoredGraph::<T, C>::red(N);
         graph.recolor(Recoloring{ old_color: 0, new_color: 1, edge: (0, 2) });
         graph.recolor(Recoloring{ old_color: 0, new_color: 1, edge: (0, 4) });
         graph.recolor(Recoloring{ old_color: 0, new_color: 1, edge: (1, 3) });
@@ -79,26 +102,113 @@ mod g6_tests {
     }
 }
 
-impl<T: Neighborhood, const C: usize, const N: usize>
-TryFrom<&Vec<String>> for ColoredGraph<T, C, N> {
-    type Error = String;
+/* N(n) from the graph6 spec: one byte up to 62, then `~` with 18 bits, then `~~` with 36 bits */
+pub fn graph6_header(n: usize) -> String {
+    let (prefix, sextets) = match n {
+        0..=62 => ("", 1),
+        63..=258047 => ("~", 3),
+        _ => ("~~", 6)
+    };
+    let mut header = String::from(prefix);
+    for i in (0..sextets).rev() {
+        header.push((((n >> (6 * i)) & 63) as u8 + 63) as char)
+    }
+    header
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Graph6Error {
+    ColorCount { expected: usize, found: usize },
+    Length { color: Color, expected: usize, found: usize },
+    BadCharacter { color: Color, position: usize, character: char },
+    VertexCount { color: Color, expected: usize, found: usize },
+    Overlap { edge: Edge, colors: (Color, Color) },
+    Uncolored(Edge)
+}
+
+impl std::fmt::Display for Graph6Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Graph6Error::ColorCount { expected, found } =>
+                write!(f, "expected {expected} g6 strings, one per color, but found {found}"),
+            Graph6Error::Length { color, expected, found } =>
+                write!(f, "color {color}: expected {expected} bytes but found {found}"),
+            Graph6Error::BadCharacter { color, position, character } =>
+                write!(f, "color {color}: {character:?} at byte {position} is not in '?'..='~'"),
+            Graph6Error::VertexCount { color, expected, found } =>
+                write!(f, "color {color}: expected {expected} vertices but found {found}"),
+            Graph6Error::Overlap { edge, colors: (c, d) } =>
+                write!(f, "edge {edge:?} has both colors {c} and {d}"),
+            Graph6Error::Uncolored(edge) => write!(f, "edge {edge:?} has no color")
+        }
+    }
+}
+
+impl std::error::Error for Graph6Error {}
+
+/* the order of a graph6 string and the bytes after its header */
+fn split_graph6_header(string: &str, color: Color) -> Result<(usize, &[u8]), Graph6Error> {
+    let bytes = string.as_bytes();
+    let bad_character = |position: usize| Graph6Error::BadCharacter {
+        color,
+        position,
+        character: string[position..].chars().next().unwrap()
+    };
+    if let Some(position) = bytes.iter().position(|byte| !(63..=126).contains(byte)) {
+        return Err(bad_character(position))
+    }
+    let (skip, sextets) = match bytes {
+        [126, 126, ..] => (2, 6),
+        [126, ..] => (1, 3),
+        _ => (0, 1)
+    };
+    if bytes.len() < skip + sextets {
+        return Err(Graph6Error::Length { color, expected: skip + sextets, found: bytes.len() })
+    }
+    let (sextets, rest) = bytes[skip..].split_at(sextets);
+    let mut n = 0;
+    for &byte in sextets {
+        n = n << 6 | (byte - 63) as usize
+    }
+    Ok((n, rest))
+}
 
-    fn try_from(strings: &Vec<String>) -> Result<Self, Self::Error> {
-        let mut neighborhoods: [[T; N]; C] = [[T::default(); N]; C];
+pub fn graph6_order(string: &str) -> Option<usize> {
+    split_graph6_header(string, 0).ok().map(|(n, _)| n)
+}
+
+pub fn matrix_order(matrix: &str) -> usize {
+    matrix
+        .split(';')
+        .filter(|row| !row.trim().is_empty())
+        .count() + 1
+}
+
+impl<T: Neighborhood, const C: usize>
+ColoredGraph<T, C> {
+    /* the coloring of K_n with one g6 string per color, as `graph6s` writes it */
+    pub fn from_graph6s(strings: &[String], n: usize) -> Result<Self, Graph6Error> {
+        if strings.len() != C {
+            return Err(Graph6Error::ColorCount { expected: C, found: strings.len() })
+        }
+        let mut graph = ColoredGraph::empty(n);
         for (c, string) in strings.iter().enumerate() {
-            let n = string.as_bytes()[0] - 63;
-            if n != (N as u8) { return Err(format!("{n} != {N}")) }
-            if n >= 63 { todo!("{N} >= 63") }
+            let (found, body) = split_graph6_header(string, c)?;
+            if found != n { return Err(Graph6Error::VertexCount { color: c, expected: n, found }) }
+            let header = string.len() - body.len();
+            let expected = choose_two(n).div_ceil(6);
+            if body.len() != expected {
+                return Err(Graph6Error::Length { color: c, expected: header + expected, found: string.len() })
+            }
             let mut pos: u8 = 5;
-            let mut i = 1;
-            let mut curr_char = string.as_bytes()[i] - 63;
-            for (u, v) in (0..N).flat_map(|v| (0..v).map(move |u| (u, v))) {
-                if pos == 5 {
-                    curr_char = string.as_bytes()[i] - 63;
-                }
+            let mut i = 0;
+            for (u, v) in (0..n).flat_map(|v| (0..v).map(move |u| (u, v))) {
+                let curr_char = body[i] - 63;
                 if is_set!(curr_char, u8, pos) {
-                    neighborhoods[c][u].add(v);
-                    neighborhoods[c][v].add(u);
+                    if let Some(d) = graph.color((u, v)) {
+                        return Err(Graph6Error::Overlap { edge: (u, v), colors: (d, c) })
+                    }
+                    graph.add(c, (u, v));
                 }
                 if pos == 0 {
                     pos = 5;
@@ -109,41 +219,145 @@ TryFrom<&Vec<String>> for ColoredGraph<T, C, N> {
                 }
             }
         }
-        Ok ( ColoredGraph::from(neighborhoods) )
+        for (u, v) in (0..n).flat_map(|v| (0..v).map(move |u| (u, v))) {
+            if graph.color((u, v)).is_none() {
+                return Err(Graph6Error::Uncolored((u, v)))
+            }
+        }
+        Ok ( graph )
     }
 }
 
 #[cfg(test)]
 mod g6_graph_conversion_tests {
-    use crate::colored_graph::ColoredGraph;
+    use crate::colored_graph::{ColoredGraph, neighborhood::{Neighborhood, U64N, U128N, U192N, U256N}};
+    use super::{graph6_header, graph6_order, Graph6Error};
+    use rand::{SeedableRng, rngs::StdRng};
     
     const C: usize = 2;
     const N: usize = 8;
     
-    type T = super::UxxN<N>;
+    type T = super::U8N;
 
     #[test]
     fn red_graph() {
-        let red = ColoredGraph::<T, C, N>::red();
+        let red = ColoredGraph::<T, C>::red(N);
         let strings = red.graph6s();
-        let red2: Result<ColoredGraph<T, C, N>, String> = ColoredGraph::try_from(&strings);
+        let red2: Result<ColoredGraph<T, C>, _> = ColoredGraph::from_graph6s(&strings, N);
         assert_eq!(Ok(red), red2)
     }
 
     #[test]
     fn random_recoloring() {
-        let mut graph = ColoredGraph::<T, C, N>::red();
-        let mut rng = rand::thread_rng();
+        let mut graph = ColoredGraph::<T, C>::red(N);
+        let mut rng = StdRng::seed_from_u64(1);
         for _ in 0..100 {
             graph.show_neighborhoods();
             let strings = graph.graph6s();
             dbg!(&strings);
-            let red2 = ColoredGraph::try_from(&strings);
+            let red2 = ColoredGraph::from_graph6s(&strings, N);
             assert!(red2.is_ok());
             assert_eq!(&graph, &red2.unwrap());
             graph.randomly_recolor(&mut rng)
         }
     }
+
+    fn check_long_random_recoloring<T: Neighborhood + std::fmt::Debug, const C: usize>(n: usize) {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut graph = ColoredGraph::<T, C>::uniformly_random(n, &mut rng);
+        for _ in 0..100 {
+            let strings = graph.graph6s();
+            assert!(strings.iter().all(|string| string.starts_with('~')));
+            assert_eq!(graph6_order(&strings[0]), Some(n));
+            assert_eq!(Ok(graph.clone()), ColoredGraph::from_graph6s(&strings, n));
+            graph.randomly_recolor(&mut rng)
+        }
+    }
+
+    #[test]
+    fn long_headers() {
+        assert_eq!(graph6_header(62), "}");
+        assert_eq!(graph6_header(63), "~??~");
+        assert_eq!(graph6_header(460175067), "~~?ZZZZZ");
+        assert_eq!(graph6_order("~~?ZZZZZ"), Some(460175067));
+        check_long_random_recoloring::<U64N, 2>(63);
+        check_long_random_recoloring::<U64N, 3>(64);
+        check_long_random_recoloring::<U128N, 2>(100);
+        check_long_random_recoloring::<U128N, 2>(128);
+        check_long_random_recoloring::<U192N, 2>(192);
+        check_long_random_recoloring::<U256N, 2>(256);
+    }
+
+    fn parse(strings: &[&str]) -> Result<ColoredGraph<T, C>, Graph6Error> {
+        ColoredGraph::from_graph6s(&strings.iter().map(|string| string.to_string()).collect::<Vec<_>>(), N)
+    }
+
+    #[test]
+    fn malformed_strings() {
+        /* McKay's DQc and its complement DlW color K_5 */
+        let parse5 = |strings: &[&str]| ColoredGraph::<T, C>::from_graph6s(
+            &strings.iter().map(|string| string.to_string()).collect::<Vec<_>>(), 5);
+        assert!(parse5(&["DQc", "DlW"]).is_ok());
+        assert_eq!(parse5(&["DQc"]), Err(Graph6Error::ColorCount { expected: 2, found: 1 }));
+        assert_eq!(parse5(&[]), Err(Graph6Error::ColorCount { expected: 2, found: 0 }));
+        assert_eq!(parse5(&["DQc", "DlW?"]), Err(Graph6Error::Length { color: 1, expected: 3, found: 4 }));
+        assert_eq!(parse5(&["DQ", "DlW"]), Err(Graph6Error::Length { color: 0, expected: 3, found: 2 }));
+        assert_eq!(parse5(&["", "DlW"]), Err(Graph6Error::Length { color: 0, expected: 1, found: 0 }));
+        assert_eq!(parse5(&["~?", "DlW"]), Err(Graph6Error::Length { color: 0, expected: 4, found: 2 }));
+        assert_eq!(parse5(&["DQc", "D l"]), Err(Graph6Error::BadCharacter { color: 1, position: 1, character: ' ' }));
+        assert_eq!(parse5(&["DQé", "DlW"]), Err(Graph6Error::BadCharacter { color: 0, position: 2, character: 'é' }));
+        assert_eq!(parse5(&["EQc", "DlW"]), Err(Graph6Error::VertexCount { color: 0, expected: 5, found: 6 }));
+        assert_eq!(parse5(&["DQc", "D~{"]), Err(Graph6Error::Overlap { edge: (0, 2), colors: (0, 1) }));
+        assert_eq!(parse5(&["DQc", "D??"]), Err(Graph6Error::Uncolored((0, 1))));
+        assert!(parse(&["G~~~~{", "G~~~~{"]).is_err());
+    }
+
+    #[test]
+    fn arbitrary_strings_never_panic() {
+        use rand::Rng;
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..10_000 {
+            let strings: Vec<String> = (0..rng.gen_range(0..4))
+                .map(|_| (0..rng.gen_range(0..12))
+                    .map(|_| rng.gen_range(' '..='~'))
+                    .collect())
+                .collect();
+            let _ = ColoredGraph::<T, C>::from_graph6s(&strings, N);
+            let _ = graph6_order(strings.first().map_or("", String::as_str));
+        }
+    }
+}
+
+#[cfg(test)]
+mod matrix_tests {
+    use crate::colored_graph::ColoredGraph;
+    use super::{graph6_order, matrix_order};
+    use rand::{SeedableRng, rngs::StdRng};
+
+    const C: usize = 3;
+    const N: usize = 8;
+
+    type T = super::U8N;
+
+    #[test]
+    fn random_recoloring() {
+        let mut graph = ColoredGraph::<T, C>::red(N);
+        let mut rng = StdRng::seed_from_u64(4);
+        for _ in 0..100 {
+            let matrix = graph.matrix();
+            assert_eq!(matrix_order(&matrix), N);
+            assert_eq!(Ok(graph.clone()), ColoredGraph::from_matrix(&matrix, N));
+            assert_eq!(graph6_order(&graph.graph6(0)), Some(N));
+            graph.randomly_recolor(&mut rng)
+        }
+    }
+
+    #[test]
+    fn bad_matrices() {
+        assert!(ColoredGraph::<T, C>::from_matrix("0; 00;", N).is_err());
+        assert!(ColoredGraph::<T, C>::from_matrix("0; 00; 000; 0000; 00000; 000000; 0000003; ", N).is_err());
+        assert!(ColoredGraph::<T, C>::from_matrix("0; 00; 000; 0000; 00000; 000000; 000000; ", N).is_err());
+    }
 }
 
 // temporarily lifting from plotters: https://docs.rs/plotters/latest/src/plotters/style/palette.rs.html#17
@@ -172,11 +386,11 @@ const COLORS: &[(u8, u8, u8)] = &[
 ];
 
 
-impl<T: Neighborhood, const C: usize, const N: usize>
-ColoredGraph<T, C, N> {
-    fn _tikz(&self) -> Vec<String> { // todo!("render at runtime w/ tectonic? best on linux...")
+impl<T: Neighborhood, const C: usize>
+ColoredGraph<T, C> {
+    pub fn tikz(&self) -> Vec<String> { // todo!("render at runtime w/ tectonic? best on linux...")
         let size_in_cm = ":2cm";
-        let mut tikz = format!(
+        let header = format!(
             "{}\n\
             {}\n\
             {}\n\
@@ -191,41 +405,45 @@ ColoredGraph<T, C, N> {
             r"  no edge/.style={dashed,draw},",
             r"  yes edge/.style={draw},",
             r"\foreach \Rotulo [count=\ci] in {0,...,",
-            N-1,
+            self.n()-1,
             r"  \node[main node] (\ci) at (\ci*360/",
-            N,
+            self.n(),
             size_in_cm,
             r") {\Rotulo};"
         );
 
-        for (u, v) in (0..N).tuple_combinations() {
-            let colored_edge = ColoredEdge { color: 0, edge: (u,v) };
-            if self.has_edge(colored_edge) {
-                tikz = format!("{tikz}\\draw ({}) [yes edge]-- ({});\n", u+1, v+1)
-            }
-            else {
-                tikz = format!("{tikz}\\draw ({}) [no edge]-- ({});\n", u+1, v+1)
+        (0..C).map(|c| {
+            let mut tikz = header.clone();
+            for (u, v) in (0..self.n()).tuple_combinations() {
+                let colored_edge = ColoredEdge { color: c, edge: (u,v) };
+                if self.has_edge(colored_edge) {
+                    tikz = format!("{tikz}\\draw ({}) [yes edge]-- ({});\n", u+1, v+1)
+                }
+                else {
+                    tikz = format!("{tikz}\\draw ({}) [no edge]-- ({});\n", u+1, v+1)
+                }
             }
-        }
 
-        tikz = format!(
-            "{tikz}{}",
-            r"\end{tikzpicture}"
-        );
-
-        vec!(tikz)
+            format!(
+                "{tikz}{}",
+                r"\end{tikzpicture}"
+            )
+        }).collect()
     }
 }
 
 #[test]
 fn can_generate_tikz() {
-    let mut rng = rand::thread_rng();
+    use rand::{SeedableRng, rngs::StdRng};
+    let mut rng = StdRng::seed_from_u64(5);
     const C: usize = 2;
     const N: usize = 8;
-    type T = UxxN<N>;
+    type T = U8N;
 
-    let graph = ColoredGraph::<T, C, N>::uniformly_random(&mut rng);
-    println!("{}", graph._tikz()[0]);
+    let graph = ColoredGraph::<T, C>::uniformly_random(N, &mut rng);
+    let pictures = graph.tikz();
+    assert_eq!(pictures.len(), C);
+    println!("{}", pictures[0]);
 }
 
 const fn usize_sqrt(n: usize) -> usize {
@@ -238,7 +456,7 @@ const fn usize_sqrt(n: usize) -> usize {
 
 use svg::{
     Document,
-    node::element::{Path, Circle, path::Data}
+    node::{Comment, element::{Path, Circle, path::Data}}
 };
 
 use super::{ColoredGraph, ColoredEdge};
@@ -249,36 +467,46 @@ pub struct GraphPics {
 }
 
 impl GraphPics {
-    pub fn render(&self) {
+    pub fn describe(mut self, description: &str) -> Self {
+        self.pics = self.pics
+            .into_iter()
+            .map(|pic| pic.add(Comment::new(format!("\n{description}"))))
+            .collect();
+        self
+    }
+
+    pub fn render(&self, dir: &std::path::Path) -> std::io::Result<()> {
+        std::fs::create_dir_all(dir)?;
         for (c, pic) in self.pics.iter().enumerate() {
             svg::save(
-                format!("plots/{}_{c}.svg", self.name), 
-                pic)
-            .unwrap();
+                dir.join(format!("{}_{c}.svg", self.name)), 
+                pic)?;
         }
+        Ok(())
     }
 }
 
-impl<T: Neighborhood, const C: usize, const N: usize>
-ColoredGraph<T, C, N> {
+impl<T: Neighborhood, const C: usize>
+ColoredGraph<T, C> {
     pub fn svg(&self, name: String) -> GraphPics {
-        let k: f64 = std::f64::consts::TAU / N as f64;
-        let r: f64 = usize_sqrt(N) as f64;
+        let n = self.n();
+        let k: f64 = std::f64::consts::TAU / n as f64;
+        let r: f64 = usize_sqrt(n) as f64;
         
-        let pos: [(f64, f64); N] = std::array::from_fn(
+        let pos: Vec<(f64, f64)> = (0..n).map(
             |i|
             {
                 let theta = k * i as f64;
                 let (sin, cos) = theta.sin_cos();
                 (r*cos, r*sin)
             }
-        );
+        ).collect();
 
         let pics = (0..C).map(|c| {
             let mut document = Document::new()
                 .set("viewBox", (-1.1*r, -1.1*r, 2.2*r, 2.2*r));
             
-            for (u, v) in (0..N).tuple_combinations() {
+            for (u, v) in (0..n).tuple_combinations() {
                 let colored_edge = ColoredEdge { color: c, edge: (u, v) };
                 if self.has_edge(colored_edge) {
                     let data = Data::new()
@@ -297,7 +525,7 @@ ColoredGraph<T, C, N> {
                 }   
             }
 
-            for (x, y) in pos {
+            for &(x, y) in &pos {
                 let node = Circle::new()
                     .set("cx", x)
                     .set("cy", y)
@@ -312,12 +540,14 @@ ColoredGraph<T, C, N> {
 
 #[test]
 fn can_generate_svg() {
+    use rand::{SeedableRng, rngs::StdRng};
     const C: usize = 3;
     const N: usize = 8;
-    type T = UxxN<N>;
+    type T = U8N;
     
-    let mut rng = rand::thread_rng();
-    let graph = ColoredGraph::<T, C, N>::uniformly_random(&mut rng);
-    let docs = graph.svg(String::from("test"));
-    docs.render()
+    let mut rng = StdRng::seed_from_u64(6);
+    let graph = ColoredGraph::<T, C>::uniformly_random(N, &mut rng);
+    let docs = graph.svg(String::from("test"))
+        .describe("s = [3, 3, 3]");
+    docs.render(std::path::Path::new("plots")).unwrap()
 }
\ No newline at end of file
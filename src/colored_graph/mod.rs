@@ -1,3 +1,5 @@
+pub mod canonical;
+pub mod cliques;
 pub mod display;
 pub mod neighborhood;
 
@@ -13,97 +15,125 @@ pub struct ColoredEdge {
     pub color: Color,
     pub edge: Edge
 }
-pub struct Recoloring<const N: usize> {
+pub struct Recoloring {
     pub old_color: Color,
     pub new_color: Color,
     pub edge: Edge
 }
 
-impl<const N: usize> Recoloring<N> {
+impl Recoloring {
     pub fn old_edge(&self) -> ColoredEdge { ColoredEdge { color: self.old_color, edge: self.edge } }
     pub fn new_edge(&self) -> ColoredEdge { ColoredEdge { color: self.new_color, edge: self.edge } }
 }
 
+/* a monochromatic clique, its vertices in increasing order */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Clique {
+    pub color: Color,
+    pub vertices: Vec<Vertex>
+}
+
+impl std::fmt::Display for Clique {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "color {}: {:?}", self.color, self.vertices)
+    }
+}
+
+/* a coloring of K_n, where n is any number of vertices that `T` holds */
 #[derive(Hash, Eq, PartialEq, Clone, Debug)]
-pub struct ColoredGraph<T: Neighborhood, const C: usize, const N: usize> {
-    neighborhoods: [[T; N]; C]
+pub struct ColoredGraph<T: Neighborhood, const C: usize> {
+    /* `neighborhoods[u][c]` are the neighbors of u in color c */
+    neighborhoods: Vec<[T; C]>
 }
 
-pub fn random_edge<const N: usize>(rng: &mut ThreadRng) -> Edge {
-    let u = rng.gen_range(0..N);
-    let v = rng.gen_range(0..N-1);
+pub fn random_edge<R: Rng + ?Sized>(n: usize, rng: &mut R) -> Edge {
+    let u = rng.gen_range(0..n);
+    let v = rng.gen_range(0..n-1);
     if v < u { (v, u) } else { (u, v+1) }
 }
 
-impl<T: Neighborhood, const C: usize, const N: usize>
-ColoredGraph<T, C, N> {
-    pub fn score(&self) -> Iyy {
+impl<T: Neighborhood, const C: usize>
+ColoredGraph<T, C> {
+    pub fn n(&self) -> usize {
+        self.neighborhoods.len()
+    }
+
+    pub fn score(&self, s: &[usize; C]) -> Iyy {
         (0..C)
-        .map(|c| self.count_cliques(c, None, None))
+        .map(|c| self.count_cliques(c, s[c], None))
         .sum()
     }
     
-    pub fn count_cliques(&self, color: Color, s: Option<usize>, candidates: Option<T>) -> Iyy {
-        let s = s.unwrap_or(S[color]);
+    pub fn count_cliques(&self, color: Color, s: usize, candidates: Option<T>) -> Iyy {
         if s == 0 { return 1 }
         
-        let candidates = candidates.unwrap_or(T::full());
+        let candidates = candidates.unwrap_or(T::full(self.n()));
         if s == 1 { return candidates.n_elements() as Iyy } 
         
         candidates.iter()
             .map(|u| candidates & self.bit_neighborhood(color, u) & T::interval_to_end(u))
-            .map(|candidates| self.count_cliques(color, Some(s-1), Some(candidates)))
+            .map(|candidates| self.count_cliques(color, s-1, Some(candidates)))
             .sum()
     }
 
-    pub fn count_edge_cliques(&self, color: Color, (u, v): Edge) -> Iyy {
+    /* `Err` with the first `limit` monochromatic cliques when there are any, since there may be far too many to list;
+       `Err` means a failure even when the list is empty, so `limit` 0 only checks */
+    pub fn verify(&self, s: &[usize; C], limit: usize) -> Result<(), Vec<Clique>> {
+        let mut cliques = s.iter()
+            .enumerate()
+            .flat_map(|(color, &s)| self.cliques(color, s, None))
+            .peekable();
+        if cliques.peek().is_none() { Ok(()) } else { Err(cliques.take(limit).collect()) }
+    }
+
+    pub fn count_edge_cliques(&self, color: Color, s: usize, (u, v): Edge) -> Iyy {
         let candidates = Some(
             self.common_neighborhood(color, u, v));
-        self.count_cliques(color, Some(S[color]-2), candidates)
+        self.count_cliques(color, s-2, candidates)
     }
     
-    pub fn red() -> ColoredGraph<T, C, N> {
-        let mut neighborhoods: [[T; N]; C] = [[T::default(); N]; C];
-        for u in 0..N {
-            neighborhoods[0][u] = T::full();
-            neighborhoods[0][u].delete(u)
+    /* n vertices without edges, to be colored by `add` */
+    fn empty(n: usize) -> ColoredGraph<T, C> {
+        assert!(n <= T::BITS, "{n} vertices do not fit in {} bits", T::BITS);
+        ColoredGraph { neighborhoods: vec![[T::default(); C]; n] }
+    }
+
+    pub fn red(n: usize) -> ColoredGraph<T, C> {
+        let mut graph = ColoredGraph::empty(n);
+        for (u, neighborhoods) in graph.neighborhoods.iter_mut().enumerate() {
+            neighborhoods[0] = T::full(n);
+            neighborhoods[0].delete(u)
         }
-        ColoredGraph { neighborhoods }
+        graph
     }
 
-    pub fn uniformly_random(rng: &mut ThreadRng) -> ColoredGraph<T, C, N> {
-        let mut neighborhoods: [[T; N]; C] = [[T::default(); N]; C];
-        for (u, v) in (0..N).tuple_combinations() {
-            let c = rng.gen_range(0..C);
-            neighborhoods[c][u].add(v);
-            neighborhoods[c][v].add(u)
+    pub fn uniformly_random<R: Rng + ?Sized>(n: usize, rng: &mut R) -> ColoredGraph<T, C> {
+        let mut graph = ColoredGraph::empty(n);
+        for (u, v) in (0..n).tuple_combinations() {
+            graph.add(rng.gen_range(0..C), (u, v))
         }
-        
-        ColoredGraph { neighborhoods }
+        graph
     }
 
-    pub fn random(rng: &mut ThreadRng, dist: &WeightedIndex<f64>) -> ColoredGraph<T, C, N> {
-        let mut neighborhoods: [[T; N]; C] = [[T::default(); N]; C];
-        for (u, v) in (0..N).tuple_combinations() {
-            let c = rng.sample(dist);
-            neighborhoods[c][u].add(v);
-            neighborhoods[c][v].add(u)
+    pub fn random<R: Rng + ?Sized>(n: usize, rng: &mut R, dist: &WeightedIndex<f64>) -> ColoredGraph<T, C> {
+        let mut graph = ColoredGraph::empty(n);
+        for (u, v) in (0..n).tuple_combinations() {
+            graph.add(rng.sample(dist), (u, v))
         }
-        
-        ColoredGraph { neighborhoods }
+        graph
     }
 
     fn add(&mut self, c: Color, (u,v): Edge) {
-        self.neighborhoods[c][u].add(v);
-        self.neighborhoods[c][v].add(u)
+        self.neighborhoods[u][c].add(v);
+        self.neighborhoods[v][c].add(u)
     }
 
     fn delete(&mut self, c: Color, (u,v): Edge) {
-        self.neighborhoods[c][u].delete(v);
-        self.neighborhoods[c][v].delete(u)
+        self.neighborhoods[u][c].delete(v);
+        self.neighborhoods[v][c].delete(u)
     }
 
-    pub fn recolor(&mut self, recolor: Recoloring<N>) {
+    pub fn recolor(&mut self, recolor: Recoloring) {
         self.delete(recolor.old_color, recolor.edge);
         self.add(recolor.new_color, recolor.edge);
     }
@@ -114,15 +144,15 @@ ColoredGraph<T, C, N> {
     }
 
     pub fn color(&self, (u, v): Edge) -> Option<Color> {
-        self.neighborhoods
+        self.neighborhoods[u]
             .iter()
             .position(
                 |neighborhood| 
-                neighborhood[u].contains(v))
+                neighborhood.contains(v))
     }
 
     pub fn bit_neighborhood(&self, color: Color, u: Vertex) -> T {
-        self.neighborhoods[color][u]
+        self.neighborhoods[u][color]
     }
 
     pub fn common_neighborhood(&self, color: Color, u: Vertex, v: Vertex) -> T {
@@ -130,14 +160,14 @@ ColoredGraph<T, C, N> {
         self.bit_neighborhood(color, v)
     }
 
-    pub fn random_edge(&self, rng: &mut ThreadRng) -> ColoredEdge {
-        let edge = random_edge::<N>(rng);
+    pub fn random_edge<R: Rng + ?Sized>(&self, rng: &mut R) -> ColoredEdge {
+        let edge = random_edge(self.n(), rng);
         let color = self.color(edge)
             .unwrap();
         ColoredEdge { color, edge }
     }
 
-    pub fn random_recoloring(&self, rng: &mut ThreadRng) -> Recoloring<N> {
+    pub fn random_recoloring<R: Rng + ?Sized>(&self, rng: &mut R) -> Recoloring {
         let colored_edge = self.random_edge(rng);
         let new_color = rng.gen_range(0..C-1);
         let new_color = 
@@ -150,36 +180,83 @@ ColoredGraph<T, C, N> {
         }
     }
 
-    pub fn randomly_recolor(&mut self, rng: &mut ThreadRng) {
+    pub fn randomly_recolor<R: Rng + ?Sized>(&mut self, rng: &mut R) {
         self.recolor(self.random_recoloring(rng))
     }
 }
 
 #[cfg(test)]
 mod tests {
-    use crate::{prelude::choose, colored_graph::neighborhood::UxxN};
+    use crate::{prelude::choose, colored_graph::neighborhood::{U8N, U192N, U256N}};
 
     use super::*;
 
-    
+    fn check_only_red_cliques<const C: usize>(s: [usize; C]) {
+        check_only_red_cliques_in::<U8N, C>(8, s)
+    }
+
+    fn check_only_red_cliques_in<T: Neighborhood, const C: usize>(n: usize, s: [usize; C]) {
+        let red = ColoredGraph::<T, C>::red(n);
+        assert_eq!(choose(n, s[0]),
+            red.count_cliques(0, s[0], None));
+        for (c, &s) in s.iter().enumerate().skip(1) {
+            assert_eq!(0,
+                red.count_cliques(c, s, None))
+        }
+        assert_eq!(choose(n, s[0]), red.score(&s))
+    }
+
     #[test]
     fn only_red_cliques() {
-        const N: usize = 8;
-        const C: usize = 2;
-        type T = UxxN<N>;
-        let red = ColoredGraph::<T, C, N>::red();
-        assert_eq!(choose(N, S[0]),
-            red.count_cliques(0, None, None));
-        for c in 1..C {
-            assert_eq!(0,
-                red.count_cliques(c, None, None))
+        check_only_red_cliques([3, 3]);
+        check_only_red_cliques([4, 4]);
+        check_only_red_cliques([3, 5]);
+        check_only_red_cliques([3, 3, 3]);
+    }
+
+    #[test]
+    fn only_red_cliques_beyond_128() {
+        check_only_red_cliques_in::<U192N, 2>(150, [3, 4]);
+        check_only_red_cliques_in::<U256N, 3>(256, [3, 3, 3]);
+    }
+
+    /* colored by the highest bit where u and v differ, so that only the blocks of 32 in the last color have triangles */
+    fn check_verify_blocks<T: Neighborhood>(n: usize) {
+        let mut graph = ColoredGraph::<T, 4>::red(n);
+        for edge in (0..n).tuple_combinations::<Edge>() {
+            let new_color = 7 - ((edge.0 ^ edge.1).ilog2() as usize).max(4);
+            if new_color != 0 { graph.recolor(Recoloring { old_color: 0, new_color, edge }) }
         }
+        let cliques = graph.verify(&[3, 3, 3, 3], usize::MAX).unwrap_err();
+        assert_eq!(cliques.len() as Iyy, (n / 32) as Iyy * choose(32, 3));
+        assert!(cliques.iter().all(|clique| clique.color == 3 && clique.vertices[0] / 32 == clique.vertices[2] / 32));
+        assert!(cliques.iter().any(|clique| clique.vertices == [n-3, n-2, n-1]));
+    }
+
+    #[test]
+    fn verify_beyond_128() {
+        check_verify_blocks::<U192N>(192);
+        check_verify_blocks::<U256N>(256);
     }
-}
 
-impl<T: Neighborhood, const C: usize, const N: usize>
-From<[[T; N]; C]> for ColoredGraph<T, C, N> {
-    fn from(neighborhoods: [[T; N]; C]) -> Self {
-        ColoredGraph { neighborhoods }
+    #[test]
+    fn verify_reports_cliques() {
+        let mut graph = ColoredGraph::<U8N, 2>::red(5);
+        for edge in [(0, 1), (1, 2), (2, 3), (3, 4), (0, 4)] {
+            graph.recolor(Recoloring { old_color: 0, new_color: 1, edge })
+        }
+        assert_eq!(graph.verify(&[3, 3], 10), Ok(()));
+        assert_eq!(graph.verify(&[2, 3], 10).unwrap_err().len(), 5);
+        assert_eq!(graph.verify(&[2, 3], 2).unwrap_err().len(), 2);
+        assert_eq!(graph.verify(&[2, 3], 0), Err(vec![]));
+
+        graph.recolor(Recoloring { old_color: 0, new_color: 1, edge: (0, 2) });
+        assert_eq!(graph.verify(&[3, 3], 10), Err(vec![
+            Clique { color: 1, vertices: vec![0, 1, 2] }
+        ]));
+        let red = ColoredGraph::<U8N, 2>::red(5);
+        let cliques = red.verify(&[4, 3], usize::MAX).unwrap_err();
+        assert_eq!(cliques.len() as Iyy, choose(5, 4));
+        assert!(cliques.iter().all(|clique| clique.color == 0 && clique.vertices.is_sorted()));
     }
-}
\ No newline at end of file
+}
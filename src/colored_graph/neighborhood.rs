@@ -6,71 +6,256 @@ use bit_iter::BitIter;
 use crate::prelude::*;
 
 pub trait Neighborhood:
-    std::cmp::PartialEq + std::cmp::Eq + std::hash::Hash + 
+    std::cmp::PartialEq + std::cmp::Eq + std::hash::Hash +
     std::clone::Clone + std::marker::Copy + std::default::Default +
-    std::ops::BitAnd<Output = Self>
+    std::ops::BitAnd<Output = Self> + Send + Sync + 'static
 {
-    fn full() -> Self;
+    /* the most vertices a neighborhood holds */
+    const BITS: usize;
+    /* the vertices u < n */
+    fn full(n: usize) -> Self;
+    /* the vertices from u on */
     fn interval_to_end(u: Vertex) -> Self;
     fn contains(&self, u: Vertex) -> bool;
-    fn iter(&self) -> BitIter<usize>;
+    type Iter: Iterator<Item = Vertex> + Clone;
+    fn iter(&self) -> Self::Iter;
     fn n_elements(&self) -> u32;
     fn add(&mut self, u: Vertex);
     fn delete(&mut self, u: Vertex);
     fn toggle(&mut self, u: Vertex);
 }
 
-#[derive(Default, Clone, Copy, Hash, PartialEq, Eq, Debug)]
-pub struct UxxN<const N: usize> {
-    bits: Uxx
+/* one bitset type per primitive width, so small N keep small hash keys */
+macro_rules! uxx_neighborhood {
+    ($name: ident, $uxx: ty) => {
+        #[derive(Default, Clone, Copy, Hash, PartialEq, Eq, Debug)]
+        pub struct $name {
+            bits: $uxx
+        }
+
+        impl BitAnd for $name {
+            type Output = $name;
+
+            fn bitand(self, rhs: Self) -> Self::Output {
+                $name { bits: self.bits & rhs.bits }
+            }
+        }
+
+        impl Neighborhood for $name {
+            const BITS: usize = <$uxx>::BITS as usize;
+
+            fn full(n: usize) -> Self {
+                $name { bits: mask!([0..n], $uxx) }
+            }
+
+            fn interval_to_end(u: Vertex) -> Self {
+                $name { bits: mask!([u..], $uxx) }
+            }
+
+            fn contains(&self, u: Vertex) -> bool {
+                let me = self.bits;
+                is_set!(me, $uxx, u)
+            }
+
+            type Iter = BitIter<$uxx>;
+
+            fn iter(&self) -> Self::Iter {
+                BitIter::from(self.bits)
+            }
+
+            fn n_elements(&self) -> u32 {
+                self.bits.count_ones()
+            }
+
+            fn add(&mut self, u: Vertex) {
+                let mut me = self.bits;
+                set!(in me, $uxx, u);
+                self.bits = me
+            }
+
+            fn delete(&mut self, u: Vertex) {
+                let mut me = self.bits;
+                unset!(in me, $uxx, u);
+                self.bits = me
+            }
+
+            fn toggle(&mut self, u: Vertex) {
+                let mut me = self.bits;
+                toggle!(in me, $uxx, u);
+                self.bits = me
+            }
+        }
+    };
+}
+
+uxx_neighborhood!(U8N, u8);
+uxx_neighborhood!(U16N, u16);
+uxx_neighborhood!(U32N, u32);
+uxx_neighborhood!(U64N, u64);
+uxx_neighborhood!(U128N, u128);
+
+/* K words of 64 bits, for N beyond the widest primitive */
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+pub struct WordsN<const K: usize> {
+    words: [u64; K]
 }
 
-impl<const N: usize> BitAnd for UxxN<N> {
-    type Output = UxxN<N>;
+pub type U192N = WordsN<3>;
+pub type U256N = WordsN<4>;
 
-    fn bitand(self, rhs: Self) -> Self::Output {
-        UxxN { bits: self.bits & rhs.bits }
+impl<const K: usize> Default for WordsN<K> {
+    fn default() -> Self {
+        WordsN { words: [0; K] }
     }
 }
 
-impl<const N: usize> Neighborhood for UxxN<N> {
-    fn full() -> Self {
-        UxxN { bits: mask!([0..N], Uxx) }
+impl<const K: usize> WordsN<K> {
+    /* the vertices u with start <= u < end */
+    fn interval(start: Vertex, end: Vertex) -> Self {
+        let mut words = [0; K];
+        for (i, word) in words.iter_mut().enumerate() {
+            let (low, high) = (start.max(64 * i), end.min(64 * (i + 1)));
+            if low < high {
+                let (low, high) = (low - 64 * i, high - 64 * i);
+                *word = mask!([low..high], u64)
+            }
+        }
+        WordsN { words }
+    }
+}
+
+impl<const K: usize> BitAnd for WordsN<K> {
+    type Output = WordsN<K>;
+
+    fn bitand(mut self, rhs: Self) -> Self::Output {
+        for (word, rhs) in self.words.iter_mut().zip(rhs.words) {
+            *word &= rhs
+        }
+        self
+    }
+}
+
+#[derive(Clone)]
+pub struct WordsIter<const K: usize> {
+    words: [u64; K],
+    word: usize
+}
+
+impl<const K: usize> Iterator for WordsIter<K> {
+    type Item = Vertex;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.word < K {
+            let bits = &mut self.words[self.word];
+            if *bits != 0 {
+                let u = bits.trailing_zeros() as Vertex;
+                *bits &= *bits - 1;
+                return Some(64 * self.word + u)
+            }
+            self.word += 1
+        }
+        None
+    }
+}
+
+impl<const K: usize> Neighborhood for WordsN<K> {
+    const BITS: usize = 64 * K;
+
+    fn full(n: usize) -> Self {
+        Self::interval(0, n)
     }
 
     fn interval_to_end(u: Vertex) -> Self {
-        UxxN { bits: mask!([u..N], Uxx) }
+        Self::interval(u, Self::BITS)
     }
-    
-    
+
     fn contains(&self, u: Vertex) -> bool {
-        let me = self.bits;
-        is_set!(me, Uxx, u)
+        let (word, bit) = (self.words[u / 64], u % 64);
+        is_set!(word, u64, bit)
     }
 
-    fn iter(&self) -> BitIter<usize> {
-        BitIter::<usize>::from(self.bits as usize)
+    type Iter = WordsIter<K>;
+
+    fn iter(&self) -> Self::Iter {
+        WordsIter { words: self.words, word: 0 }
     }
 
     fn n_elements(&self) -> u32 {
-        self.bits.count_ones()
+        self.words.iter().map(|word| word.count_ones()).sum()
     }
-    
+
     fn add(&mut self, u: Vertex) {
-        let mut me = self.bits;
-        set!(in me, Uxx, u);
-        self.bits = me
+        let (mut word, bit) = (self.words[u / 64], u % 64);
+        set!(in word, u64, bit);
+        self.words[u / 64] = word
     }
 
     fn delete(&mut self, u: Vertex) {
-        let mut me = self.bits;
-        unset!(in me, Uxx, u);
-        self.bits = me
+        let (mut word, bit) = (self.words[u / 64], u % 64);
+        unset!(in word, u64, bit);
+        self.words[u / 64] = word
     }
 
     fn toggle(&mut self, u: Vertex) {
-        let mut me = self.bits;
-        toggle!(in me, Uxx, u);
-        self.bits = me
+        let (mut word, bit) = (self.words[u / 64], u % 64);
+        toggle!(in word, u64, bit);
+        self.words[u / 64] = word
+    }
+}
+
+#[cfg(test)]
+mod uxx_tests {
+    use super::*;
+
+    #[test]
+    fn iter_every_width() {
+        assert_eq!(U64N::full(64).iter().collect::<Vec<_>>(), (0..64).collect::<Vec<_>>());
+        assert_eq!(U128N::full(70).iter().collect::<Vec<_>>(), (0..70).collect::<Vec<_>>());
+        assert_eq!(U128N::interval_to_end(60).iter().collect::<Vec<_>>(), (60..128).collect::<Vec<_>>());
+        assert_eq!(U8N::full(3) & U8N::interval_to_end(1), U8N { bits: 0b110 });
+    }
+}
+
+#[cfg(test)]
+mod words_tests {
+    use rand::{SeedableRng, rngs::StdRng};
+    use super::*;
+
+    fn check_words<const K: usize, const N: usize>() {
+        assert_eq!(WordsN::<K>::full(N).iter().collect::<Vec<_>>(), (0..N).collect::<Vec<_>>());
+        for u in [0, 1, 63, 64, 65, 127, 128, N - 1] {
+            let to_end = WordsN::<K>::full(N) & WordsN::<K>::interval_to_end(u);
+            assert_eq!(to_end.iter().collect::<Vec<_>>(), (u..N).collect::<Vec<_>>());
+        }
+
+        let mut rng = StdRng::seed_from_u64(1);
+        let (mut a, mut b) = (WordsN::<K>::default(), WordsN::<K>::default());
+        let mut expected = [[false; N]; 2];
+        for _ in 0..4 * N {
+            let (u, v) = (rand::Rng::gen_range(&mut rng, 0..N), rand::Rng::gen_range(&mut rng, 0..N));
+            a.toggle(u);
+            expected[0][u] ^= true;
+            b.add(v);
+            expected[1][v] = true;
+            if u % 3 == 0 {
+                b.delete(u);
+                expected[1][u] = false
+            }
+        }
+        let both: Vec<Vertex> = (0..N).filter(|&u| expected[0][u] && expected[1][u]).collect();
+        assert_eq!((a & b).iter().collect::<Vec<_>>(), both);
+        assert_eq!((a & b).n_elements() as usize, both.len());
+        for (u, (&in_a, &in_b)) in expected[0].iter().zip(&expected[1]).enumerate() {
+            assert_eq!(a.contains(u), in_a);
+            assert_eq!(b.contains(u), in_b)
+        }
+    }
+
+    #[test]
+    fn words_match_bools() {
+        check_words::<3, 129>();
+        check_words::<3, 150>();
+        check_words::<3, 192>();
+        check_words::<4, 256>();
     }
 }
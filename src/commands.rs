@@ -0,0 +1,223 @@
+use std::io::{self, IsTerminal};
+use std::path::Path;
+use std::process::ExitCode;
+use std::time::Duration;
+
+use mcts_ramsey::{prelude::*, strategy::{SearchError, search_config, resume_config}, dispatch::*};
+use mcts_ramsey::colored_graph::{ColoredGraph, neighborhood::Neighborhood, display::*};
+
+use crate::cli::*;
+
+pub enum Failure {
+    InvalidConfig(String),
+    BudgetExhausted,
+    /* quit at the confirmation prompt, before searching */
+    Aborted,
+    Error(String)
+}
+
+impl Failure {
+    /* 0 is success, and clap already exits with 2 on malformed arguments, so an invalid config gets its own code */
+    pub fn exit_code(&self) -> ExitCode {
+        match self {
+            Failure::Error(_) => ExitCode::from(1),
+            Failure::BudgetExhausted => ExitCode::from(3),
+            Failure::InvalidConfig(_) => ExitCode::from(4),
+            Failure::Aborted => ExitCode::from(5)
+        }
+    }
+}
+
+impl std::fmt::Display for Failure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Failure::InvalidConfig(error) => write!(f, "Invalid config: {error}"),
+            Failure::BudgetExhausted => write!(f, "No witness found within the budget"),
+            Failure::Aborted => write!(f, "Search aborted"),
+            Failure::Error(error) => write!(f, "{error}")
+        }
+    }
+}
+
+impl From<String> for Failure {
+    fn from(error: String) -> Self {
+        Failure::Error(error)
+    }
+}
+
+pub fn search(args: SearchArgs) -> Result<(), Failure> {
+    let resumed = args.resumed()
+        .map_err(|error| Failure::InvalidConfig(error.to_string()))?;
+    let config = args.config(resumed.as_ref())
+        .and_then(|config| config.validate().map(|()| config.seeded()))
+        .map_err(|error| Failure::InvalidConfig(error.to_string()))?;
+
+    if let Some(checkpoint) = &resumed {
+        println!("Resuming epoch {}, episode {} after {:.3}s.", checkpoint.state.epoch, checkpoint.state.episode, checkpoint.elapsed);
+    }
+    println!("Goal: prove R{:?} > {}.", config.s, config.n);
+    print!("{}", config.to_toml());
+    println!("Initial color probabilities: {:?}", config.guess_p());
+
+    if !args.yes && io::stdin().is_terminal() {
+        println!("Enter 'q' now to quit.");
+        let mut input = String::new();
+        io::stdin()
+            .read_line(&mut input)
+            .map_err(|error| error.to_string())?;
+        if input.contains('q') { return Err(Failure::Aborted) }
+    }
+
+    let outcome = match &resumed {
+        Some(checkpoint) => resume_config(&config, checkpoint),
+        None => search_config(&config)
+    }.map_err(|error| match error {
+        SearchError::Config(error) => Failure::InvalidConfig(error.to_string()),
+        error => Failure::Error(error.to_string())
+    })?;
+
+    println!("Elapsed: {:.3?}", Duration::from_secs_f64(outcome.stats.elapsed));
+    if outcome.witness().is_some() { Ok(()) } else { Err(Failure::BudgetExhausted) }
+}
+
+fn g6_dimensions(graphs: &[String]) -> Result<(usize, usize), String> {
+    let n = graphs
+        .first()
+        .and_then(|graph| graph6_order(graph))
+        .ok_or("expected g6 strings")?;
+    Ok((graphs.len(), n))
+}
+
+struct Verify<'a> {
+    graphs: &'a Vec<String>,
+    s: &'a [usize],
+    max_cliques: usize
+}
+
+impl Dispatch for Verify<'_> {
+    type Output = Result<bool, String>;
+    fn run<T: Neighborhood, const C: usize>(self, n: usize) -> Self::Output {
+        let graph = ColoredGraph::<T, C>::from_graph6s(self.graphs, n).map_err(|error| error.to_string())?;
+        let s: [usize; C] = self.s
+            .try_into()
+            .map_err(|_| format!("{} clique sizes for {C} colors", self.s.len()))?;
+        let counts: Vec<Iyy> = s.iter()
+            .enumerate()
+            .map(|(c, &s)| graph.count_cliques(c, s, None))
+            .collect();
+        for (c, (&s, count)) in s.iter().zip(&counts).enumerate() {
+            let copies = if *count == 1 { "copy" } else { "copies" };
+            println!("color {c}: {count} {copies} of K_{s}");
+        }
+        let Err(cliques) = graph.verify(&s, self.max_cliques) else { return Ok(true) };
+        for clique in &cliques {
+            println!("{clique}");
+        }
+        let total: Iyy = counts.iter().sum();
+        if total > cliques.len() as Iyy {
+            println!("... and {} more", total - cliques.len() as Iyy);
+        }
+        Ok(false)
+    }
+}
+
+pub fn verify(args: VerifyArgs) -> Result<(), Failure> {
+    let (c, n) = g6_dimensions(&args.graphs)?;
+    if args.s.len() != c {
+        return Err(Failure::Error(format!("{} clique sizes for {c} colors", args.s.len())))
+    }
+    let job = Verify { graphs: &args.graphs, s: &args.s, max_cliques: args.max_cliques };
+    if dispatch(c, n, job).map_err(|error| error.to_string())?? {
+        println!("R{:?} > {n}", args.s);
+        Ok(())
+    }
+    else {
+        Err(Failure::Error(format!("not a witness to R{:?} > {n}", args.s)))
+    }
+}
+
+struct Render<'a> {
+    graphs: &'a Vec<String>,
+    format: PictureFormat,
+    name: String,
+    dir: &'a Path
+}
+
+impl Dispatch for Render<'_> {
+    type Output = Result<(), String>;
+    fn run<T: Neighborhood, const C: usize>(self, n: usize) -> Self::Output {
+        let graph = ColoredGraph::<T, C>::from_graph6s(self.graphs, n).map_err(|error| error.to_string())?;
+        let written = match self.format {
+            PictureFormat::Svg => graph.svg(self.name.clone()).render(self.dir),
+            PictureFormat::Tikz => std::fs::create_dir_all(self.dir).and_then(|()| {
+                graph.tikz()
+                    .iter()
+                    .enumerate()
+                    .try_for_each(|(c, tikz)| std::fs::write(self.dir.join(format!("{}_{c}.tex", self.name)), tikz))
+            })
+        };
+        written.map_err(|error| format!("{}: {error}", self.dir.display()))?;
+        println!("Check out {}/{}*", self.dir.display(), self.name);
+        Ok(())
+    }
+}
+
+pub fn render(args: RenderArgs) -> Result<(), Failure> {
+    let (c, n) = g6_dimensions(&args.graphs)?;
+    let job = Render { graphs: &args.graphs, format: args.format, name: args.name, dir: &args.dir };
+    Ok(dispatch(c, n, job).map_err(|error| error.to_string())??)
+}
+
+struct Convert<'a> {
+    graphs: &'a Vec<String>,
+    from: GraphFormat,
+    to: GraphFormat
+}
+
+impl Dispatch for Convert<'_> {
+    type Output = Result<(), String>;
+    fn run<T: Neighborhood, const C: usize>(self, n: usize) -> Self::Output {
+        let graph = match self.from {
+            GraphFormat::G6 => ColoredGraph::<T, C>::from_graph6s(self.graphs, n).map_err(|error| error.to_string())?,
+            GraphFormat::Matrix => ColoredGraph::<T, C>::from_matrix(&self.graphs[0], n)?
+        };
+        match self.to {
+            GraphFormat::G6 => println!("{}", graph.graph6s().join(" ")),
+            GraphFormat::Matrix => println!("{}", graph.matrix())
+        }
+        Ok(())
+    }
+}
+
+pub fn convert(args: ConvertArgs) -> Result<(), Failure> {
+    let (c, n) = match args.from {
+        GraphFormat::G6 => g6_dimensions(&args.graphs)?,
+        GraphFormat::Matrix => {
+            let [matrix] = args.graphs.as_slice()
+                else { return Err(Failure::Error(String::from("expected a single matrix"))) };
+            let largest_color = matrix
+                .chars()
+                .filter_map(|c| c.to_digit(10))
+                .max()
+                .unwrap_or(0) as Color;
+            let c = args.colors.unwrap_or(2.max(largest_color + 1));
+            (c, matrix_order(matrix))
+        }
+    };
+    let job = Convert { graphs: &args.graphs, from: args.from, to: args.to };
+    Ok(dispatch(c, n, job).map_err(|error| error.to_string())??)
+}
+
+pub fn clean(args: CleanArgs) -> Result<(), Failure> {
+    /* https://stackoverflow.com/a/69987121 */
+    let paths = std::fs::read_dir(&args.dir)
+        .map_err(|error| format!("{}: {error}", args.dir.display()))?;
+    for path in paths {
+        let path = path.map_err(|error| error.to_string())?.path();
+        if path.extension() == Some(std::ffi::OsStr::new("svg")) {
+            std::fs::remove_file(path).map_err(|error| error.to_string())?;
+        }
+    }
+    println!("{}/*.svg cleared 😊", args.dir.display());
+    Ok(())
+}
@@ -0,0 +1,661 @@
+use std::{fmt, path::{Path, PathBuf}};
+
+use serde::{Deserialize, Serialize};
+
+use crate::prelude::Uzz;
+
+pub const MIN_N: usize = 2;
+pub const MAX_N: usize = 256;
+pub const MIN_C: usize = 2;
+pub const MAX_C: usize = 4;
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchConfig {
+    pub s: Vec<usize>,
+    pub n: usize,
+    pub epochs: usize,
+    pub episodes: Uzz,
+    pub roots: usize,
+    pub explore: f64,
+    /* key visit counts and tied roots by canonical labeling, merging relabeled colorings */
+    pub canonical: bool,
+    /* likewise for permutations of colors with equal clique sizes */
+    pub permute_colors: bool,
+    pub guess_p: GuessP,
+    /* seeds every random choice of a single-threaded search, and is recorded with the results */
+    pub seed: Option<u64>,
+    /* in seconds */
+    pub time_limit: Option<f64>,
+    pub output_dir: PathBuf,
+    /* worker threads playing MCTS episodes */
+    pub threads: usize,
+    /* where to write checkpoints, if anywhere */
+    pub checkpoint: Option<PathBuf>,
+    /* in seconds */
+    pub checkpoint_every: f64,
+    /* also save the MCTS visit counts, which can be large */
+    pub checkpoint_visits: bool,
+    pub strategy: Strategy,
+    pub tabu: TabuConfig,
+    pub annealing: AnnealingConfig,
+    pub memory: MemoryConfig,
+    pub selection: SelectionConfig,
+    pub length: LengthConfig
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ConfigError {
+    UnsupportedC(usize),
+    BadS(Vec<usize>),
+    UnsupportedN(usize),
+    Zero(&'static str),
+    BadExplore(f64),
+    GuessPLength { expected: usize, found: usize },
+    BadGuessP(Vec<f64>),
+    BadTimeLimit(f64),
+    BadTemperatures { initial: f64, last: f64 },
+    BadCheckpointEvery(f64),
+    BadMemory(f64),
+    BadExploreDecay(f64),
+    BadSelectionTemperature(f64),
+    BadDiscount(f64),
+    BadLengthFactor(f64),
+    BadCheckpoint(String),
+    /* a field a resumed search must keep from its checkpoint, as (name, saved, given) */
+    ResumeChanged { field: &'static str, saved: String, given: String },
+    Read { path: PathBuf, error: String },
+    UnknownFormat(PathBuf),
+    Format { path: PathBuf, error: String }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::UnsupportedC(c) => write!(f, "{c} colors is not in {MIN_C}..={MAX_C}"),
+            ConfigError::BadS(s) => write!(f, "S = {s:?} has a clique size below 2"),
+            ConfigError::UnsupportedN(n) => write!(f, "N = {n} is not in {MIN_N}..={MAX_N}"),
+            ConfigError::Zero(name) => write!(f, "{name} must be positive"),
+            ConfigError::BadExplore(explore) => write!(f, "EXPLORE = {explore} must be finite and nonnegative"),
+            ConfigError::GuessPLength { expected, found } =>
+                write!(f, "GUESS_P has {found} entries but there are {expected} colors"),
+            ConfigError::BadGuessP(p) => write!(f, "GUESS_P = {p:?} is not a valid weighting"),
+            ConfigError::BadTimeLimit(time_limit) => write!(f, "time limit {time_limit} must be positive"),
+            ConfigError::BadCheckpointEvery(every) => write!(f, "checkpoint interval {every} must be positive"),
+            ConfigError::BadMemory(megabytes) => write!(f, "memory cap of {megabytes} MB must be positive"),
+            ConfigError::BadExploreDecay(decay) => write!(f, "exploration decay {decay} must be finite and positive"),
+            ConfigError::BadSelectionTemperature(temperature) =>
+                write!(f, "selection temperature {temperature} must be finite and positive, or 0 for no softmax"),
+            ConfigError::BadDiscount(discount) => write!(f, "discount {discount} is not in 0..=1"),
+            ConfigError::BadLengthFactor(factor) => write!(f, "episode length factor {factor} must be finite and positive"),
+            ConfigError::BadCheckpoint(error) => write!(f, "could not resume: {error}"),
+            ConfigError::ResumeChanged { field, saved, given } =>
+                write!(f, "could not resume: {field} = {given} but the checkpoint has {field} = {saved}"),
+            ConfigError::BadTemperatures { initial, last } =>
+                write!(f, "temperatures must satisfy 0 < {last} <= {initial} < inf"),
+            ConfigError::Read { path, error } => write!(f, "could not read {}: {error}", path.display()),
+            ConfigError::UnknownFormat(path) => write!(f, "{} is neither .toml nor .json", path.display()),
+            ConfigError::Format { path, error } => write!(f, "could not parse {}: {error}", path.display())
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
+impl Default for SearchConfig {
+    fn default() -> Self {
+        SearchConfig {
+            s: vec![3, 3],
+            n: 5,
+            epochs: 50,
+            episodes: 10_000,
+            roots: 250,
+            explore: 4.5,
+            canonical: false,
+            permute_colors: false,
+            guess_p: GuessP::Uniform,
+            seed: None,
+            time_limit: None,
+            output_dir: PathBuf::from("plots"),
+            threads: 1,
+            checkpoint: None,
+            checkpoint_every: 300.0,
+            checkpoint_visits: false,
+            strategy: Strategy::Mcts,
+            tabu: TabuConfig::default(),
+            annealing: AnnealingConfig::default(),
+            memory: MemoryConfig::default(),
+            selection: SelectionConfig::default(),
+            length: LengthConfig::default()
+        }
+    }
+}
+
+impl SearchConfig {
+    pub fn c(&self) -> usize {
+        self.s.len()
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !(MIN_C..=MAX_C).contains(&self.c()) { return Err(ConfigError::UnsupportedC(self.c())) }
+        if self.s.iter().any(|&s| s < 2) { return Err(ConfigError::BadS(self.s.clone())) }
+        if !(MIN_N..=MAX_N).contains(&self.n) { return Err(ConfigError::UnsupportedN(self.n)) }
+        if self.epochs == 0 { return Err(ConfigError::Zero("EPOCHS")) }
+        if self.episodes == 0 { return Err(ConfigError::Zero("EPISODES")) }
+        if self.roots == 0 { return Err(ConfigError::Zero("ROOTS")) }
+        if self.threads == 0 { return Err(ConfigError::Zero("THREADS")) }
+        if !self.explore.is_finite() || self.explore < 0.0 {
+            return Err(ConfigError::BadExplore(self.explore))
+        }
+        if let GuessP::Weights(guess_p) = &self.guess_p {
+            if guess_p.len() != self.c() {
+                return Err(ConfigError::GuessPLength { expected: self.c(), found: guess_p.len() })
+            }
+            if guess_p.iter().any(|p| !p.is_finite() || *p < 0.0)
+            || guess_p.iter().sum::<f64>() <= 0.0 {
+                return Err(ConfigError::BadGuessP(guess_p.clone()))
+            }
+        }
+        if let Some(time_limit) = self.time_limit {
+            if time_limit.is_nan() || time_limit <= 0.0 { return Err(ConfigError::BadTimeLimit(time_limit)) }
+        }
+        if self.checkpoint_every.is_nan() || self.checkpoint_every <= 0.0 {
+            return Err(ConfigError::BadCheckpointEvery(self.checkpoint_every))
+        }
+        let AnnealingConfig { temperature: initial, final_temperature: last, .. } = self.annealing;
+        if !initial.is_finite() || last.is_nan() || last <= 0.0 || last > initial {
+            return Err(ConfigError::BadTemperatures { initial, last })
+        }
+        if self.annealing.patience == 0 { return Err(ConfigError::Zero("PATIENCE")) }
+        if self.annealing.tournament == 0 { return Err(ConfigError::Zero("TOURNAMENT")) }
+        if self.memory.max_graphs == Some(0) { return Err(ConfigError::Zero("MAX_GRAPHS")) }
+        if let Some(megabytes) = self.memory.max_megabytes {
+            if megabytes.is_nan() || megabytes <= 0.0 { return Err(ConfigError::BadMemory(megabytes)) }
+        }
+        let SelectionConfig { explore_decay, softmax_temperature, prior_temperature, discount, .. } = self.selection;
+        if !explore_decay.is_finite() || explore_decay <= 0.0 {
+            return Err(ConfigError::BadExploreDecay(explore_decay))
+        }
+        if !softmax_temperature.is_finite() || softmax_temperature < 0.0 {
+            return Err(ConfigError::BadSelectionTemperature(softmax_temperature))
+        }
+        if !prior_temperature.is_finite() || prior_temperature <= 0.0 {
+            return Err(ConfigError::BadSelectionTemperature(prior_temperature))
+        }
+        if !(0.0..=1.0).contains(&discount) { return Err(ConfigError::BadDiscount(discount)) }
+        if self.length.moves == Some(0) { return Err(ConfigError::Zero("MOVES")) }
+        if self.length.max_moves == Some(0) { return Err(ConfigError::Zero("MAX_MOVES")) }
+        for factor in [self.length.ratio, self.length.slack] {
+            if !factor.is_finite() || factor <= 0.0 { return Err(ConfigError::BadLengthFactor(factor)) }
+        }
+        Ok(())
+    }
+
+    pub fn guess_p(&self) -> Vec<f64> {
+        self.guess_p.resolve(&self.s, self.n)
+    }
+
+    /* `Ok` when this config continues the same search as `saved`, which only lets budget and runtime knobs change */
+    pub fn check_resume(&self, saved: &SearchConfig) -> Result<(), ConfigError> {
+        fn same<V: PartialEq + fmt::Debug>(field: &'static str, saved: &V, given: &V) -> Result<(), ConfigError> {
+            if saved == given { return Ok(()) }
+            Err(ConfigError::ResumeChanged { field, saved: format!("{saved:?}"), given: format!("{given:?}") })
+        }
+        same("S", &saved.s, &self.s)?;
+        same("N", &saved.n, &self.n)?;
+        same("STRATEGY", &saved.strategy, &self.strategy)?;
+        same("CANONICAL", &saved.canonical, &self.canonical)?;
+        same("PERMUTE_COLORS", &saved.permute_colors, &self.permute_colors)?;
+        same("BACKUP", &saved.selection.backup, &self.selection.backup)
+    }
+
+    /* with a fresh random seed unless one was given, so that every run can be repeated */
+    pub fn seeded(mut self) -> Self {
+        self.seed.get_or_insert_with(rand::random);
+        self
+    }
+
+    /* the format is chosen by the extension, `.toml` or `.json` */
+    pub fn load(path: &Path) -> Result<SearchConfig, ConfigError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|error| ConfigError::Read { path: path.to_path_buf(), error: error.to_string() })?;
+        let format_error = |error: String| ConfigError::Format { path: path.to_path_buf(), error };
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("toml") => toml::from_str(&contents).map_err(|error| format_error(error.to_string())),
+            Some("json") => serde_json::from_str(&contents).map_err(|error| format_error(error.to_string())),
+            _ => Err(ConfigError::UnknownFormat(path.to_path_buf()))
+        }
+    }
+
+    pub fn to_toml(&self) -> String {
+        toml::to_string(self)
+            .expect("SearchConfig is plain data")
+    }
+}
+
+pub fn uniform(c: usize) -> Vec<f64> {
+    vec![1_f64 / (c as f64); c]
+}
+
+/* a lowercase enum, spelled the same in experiment files and on the command line */
+macro_rules! named_enum {
+    ($name: ident { $($variant: ident = $string: literal),* }) => {
+        #[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+        #[serde(rename_all = "lowercase")]
+        pub enum $name {
+            $($variant),*
+        }
+
+        impl std::str::FromStr for $name {
+            type Err = String;
+
+            fn from_str(string: &str) -> Result<Self, Self::Err> {
+                match string.trim() {
+                    $($string => Ok($name::$variant),)*
+                    _ => Err(format!("{string:?} is not one of {}", [$($string),*].join(", ")))
+                }
+            }
+        }
+    };
+}
+
+named_enum!(Strategy { Mcts = "mcts", Tabu = "tabu", Annealing = "annealing" });
+named_enum!(Schedule { Geometric = "geometric", Linear = "linear", Adaptive = "adaptive" });
+named_enum!(Proposal { Uniform = "uniform", Slope = "slope" });
+named_enum!(Eviction { Visits = "visits", Lru = "lru", Age = "age" });
+named_enum!(Policy { Uct = "uct", Ucb1 = "ucb1", Puct = "puct" });
+named_enum!(Backup { Slope = "slope", Mean = "mean", Max = "max", Discounted = "discounted" });
+named_enum!(Length { Linear = "linear", Constant = "constant", Geometric = "geometric", Luby = "luby", Adaptive = "adaptive" });
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TabuConfig {
+    /* moves for which a recolored edge may not be recolored again */
+    pub tenure: usize,
+    /* allow tabu moves that beat the best score so far */
+    pub aspiration: bool
+}
+
+impl Default for TabuConfig {
+    fn default() -> Self {
+        TabuConfig { tenure: 20, aspiration: true }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AnnealingConfig {
+    /* each epoch cools from `temperature` to `final_temperature` */
+    pub temperature: f64,
+    pub final_temperature: f64,
+    pub schedule: Schedule,
+    /* adaptive schedules reheat after this many steps without a new best */
+    pub patience: Uzz,
+    pub proposal: Proposal,
+    /* slope proposals take the steepest of this many uniform recolorings */
+    pub tournament: usize
+}
+
+impl Default for AnnealingConfig {
+    fn default() -> Self {
+        AnnealingConfig {
+            temperature: 2.0,
+            final_temperature: 0.05,
+            schedule: Schedule::Geometric,
+            patience: 2_000,
+            proposal: Proposal::Uniform,
+            tournament: 4
+        }
+    }
+}
+
+/* moves per MCTS episode in each epoch, `E/4 + epoch` by default */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct LengthConfig {
+    /* linear: MOVES + increment epoch, constant: MOVES, geometric: MOVES ratio^(epoch - 1),
+       luby: MOVES times the Luby sequence, adaptive: slack times the depth of the latest improvement, at least MOVES */
+    pub schedule: Length,
+    /* E/4 unless given */
+    pub moves: Option<usize>,
+    pub increment: usize,
+    pub ratio: f64,
+    pub slack: f64,
+    pub max_moves: Option<usize>
+}
+
+impl Default for LengthConfig {
+    fn default() -> Self {
+        LengthConfig { schedule: Length::Linear, moves: None, increment: 1, ratio: 1.1, slack: 2.0, max_moves: None }
+    }
+}
+
+/* caps on the MCTS visit statistics, which are unbounded by default */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct MemoryConfig {
+    pub max_graphs: Option<usize>,
+    /* of the estimated size of the visit statistics */
+    pub max_megabytes: Option<f64>,
+    /* which graphs go first: the least visited, least recently visited or first visited */
+    pub eviction: Eviction
+}
+
+impl Default for MemoryConfig {
+    fn default() -> Self {
+        MemoryConfig { max_graphs: None, max_megabytes: None, eviction: Eviction::Visits }
+    }
+}
+
+impl MemoryConfig {
+    pub fn capped(&self) -> bool {
+        self.max_graphs.is_some() || self.max_megabytes.is_some()
+    }
+}
+
+/* how MCTS episodes trade the slope of an action against how often it was taken */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SelectionConfig {
+    /* uct: q + EXPLORE sqrt(n) / (1 + n_a), ucb1: q + EXPLORE sqrt(ln n / n_a), puct: uct scaled by a prior */
+    pub policy: Policy,
+    /* EXPLORE is multiplied by this every epoch */
+    pub explore_decay: f64,
+    /* sample actions with weights exp(score / temperature) rather than take the best, unless 0 */
+    pub softmax_temperature: f64,
+    /* puct's prior is a softmax of the slopes at this temperature */
+    pub prior_temperature: f64,
+    /* the value q of a taken action: its slope when first taken, or the mean or max over the episodes taking it
+       of the best improvement reached afterwards, or the mean discounted sum of the slopes that followed */
+    pub backup: Backup,
+    pub discount: f64
+}
+
+impl Default for SelectionConfig {
+    fn default() -> Self {
+        SelectionConfig {
+            policy: Policy::Uct,
+            explore_decay: 1.0,
+            softmax_temperature: 0.0,
+            prior_temperature: 1.0,
+            backup: Backup::Slope,
+            discount: 0.9
+        }
+    }
+}
+
+/* the initial color probabilities, written `"uniform"`, `"auto"` or `[0.3, 0.7]` */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "GuessPRepr", into = "GuessPRepr")]
+pub enum GuessP {
+    Uniform,
+    Auto,
+    Weights(Vec<f64>)
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum GuessPRepr {
+    Name(String),
+    Weights(Vec<f64>)
+}
+
+impl TryFrom<GuessPRepr> for GuessP {
+    type Error = String;
+
+    fn try_from(repr: GuessPRepr) -> Result<Self, Self::Error> {
+        match repr {
+            GuessPRepr::Name(name) => name.parse(),
+            GuessPRepr::Weights(weights) => Ok(GuessP::Weights(weights))
+        }
+    }
+}
+
+impl From<GuessP> for GuessPRepr {
+    fn from(guess_p: GuessP) -> Self {
+        match guess_p {
+            GuessP::Uniform => GuessPRepr::Name(String::from("uniform")),
+            GuessP::Auto => GuessPRepr::Name(String::from("auto")),
+            GuessP::Weights(weights) => GuessPRepr::Weights(weights)
+        }
+    }
+}
+
+impl std::str::FromStr for GuessP {
+    type Err = String;
+
+    fn from_str(string: &str) -> Result<Self, Self::Err> {
+        match string.trim() {
+            "uniform" => Ok(GuessP::Uniform),
+            "auto" => Ok(GuessP::Auto),
+            weights => weights
+                .split(',')
+                .map(|p| p.trim().parse())
+                .collect::<Result<_, _>>()
+                .map(GuessP::Weights)
+                .map_err(|_| format!("{string:?} is not \"uniform\", \"auto\" or comma-separated weights"))
+        }
+    }
+}
+
+impl GuessP {
+    pub fn resolve(&self, s: &[usize], n: usize) -> Vec<f64> {
+        match self {
+            GuessP::Uniform => uniform(s.len()),
+            GuessP::Auto => auto_guess_p(s, n),
+            GuessP::Weights(weights) => weights.clone()
+        }
+    }
+}
+
+/* choose(n, k) in floating point, since it overflows an Iyy long before the supported n and s run out */
+fn binomial(n: usize, k: usize) -> f64 {
+    if k > n { return 0.0 }
+    let k = k.min(n - k);
+    (0..k).fold(1.0, |binomial, i| binomial * (n - i) as f64 / (i + 1) as f64)
+}
+
+/* sum over c of choose(n, s[c]) * p[c]^choose(s[c], 2), the expected cost of a random coloring */
+pub fn expected_cliques(p: &[f64], s: &[usize], n: usize) -> f64 {
+    p.iter()
+        .zip(s)
+        .map(|(&p, &s)| binomial(n, s) * p.powf(binomial(s, 2)))
+        .sum()
+}
+
+/*
+ * minimizes `expected_cliques` over the simplex: at the optimum each p[c] > 0 has
+ * d/dp[c] = choose(n, s[c]) * k * p[c]^(k-1) equal to a common multiplier, with k = choose(s[c], 2),
+ * so we bisect on the multiplier until the p[c] sum to 1
+ */
+fn auto_guess_p(s: &[usize], n: usize) -> Vec<f64> {
+    let coefficients: Vec<(f64, f64)> = s.iter()
+        .map(|&s| (binomial(n, s), binomial(s, 2)))
+        .collect();
+
+    /* a color without any K_s[c] in K_n is free */
+    if coefficients.iter().any(|&(a, _)| a == 0.0) {
+        let free: Vec<f64> = coefficients.iter()
+            .map(|&(a, _)| if a == 0.0 { 1.0 } else { 0.0 })
+            .collect();
+        let total: f64 = free.iter().sum();
+        return free.iter().map(|p| p / total).collect()
+    }
+
+    /* an s = 2 color costs choose(n, 2) * p, so it only takes what the curved colors leave over */
+    let cheapest_linear = (0..s.len())
+        .filter(|&c| coefficients[c].1 <= 1.0)
+        .min_by(|&c, &d| coefficients[c].0.total_cmp(&coefficients[d].0));
+    let p_at = |lambda: f64| -> Vec<f64> {
+        coefficients.iter()
+            .map(|&(a, k)| {
+                if k <= 1.0 { 0.0 }
+                else { (lambda / (a * k)).powf(1.0 / (k - 1.0)).min(1.0) }
+            })
+            .collect()
+    };
+
+    let mut hi = coefficients.iter()
+        .map(|&(a, k)| a * k)
+        .fold(0_f64, f64::max);
+    if let Some(c) = cheapest_linear {
+        hi = hi.min(coefficients[c].0);
+        let mut p = p_at(hi);
+        let curved: f64 = p.iter().sum();
+        if curved < 1.0 {
+            p[c] = 1.0 - curved;
+            return p
+        }
+    }
+    let mut lo = 0_f64;
+    for _ in 0..200 {
+        let mid = (lo + hi) / 2.0;
+        if p_at(mid).iter().sum::<f64>() < 1.0 { lo = mid } else { hi = mid }
+    }
+
+    let p = p_at(hi);
+    let total: f64 = p.iter().sum();
+    p.iter().map(|p| p / total).collect()
+}
+
+#[cfg(test)]
+mod config_tests {
+    use super::*;
+
+    #[test]
+    fn default_is_valid() {
+        assert_eq!(SearchConfig::default().validate(), Ok(()))
+    }
+
+    #[test]
+    fn rejects_bad_values() {
+        let config = SearchConfig { n: MAX_N + 1, ..Default::default() };
+        assert_eq!(config.validate(), Err(ConfigError::UnsupportedN(MAX_N + 1)));
+        let config = SearchConfig { episodes: 0, ..Default::default() };
+        assert_eq!(config.validate(), Err(ConfigError::Zero("EPISODES")));
+        let config = SearchConfig { explore: f64::NAN, ..Default::default() };
+        assert!(config.validate().is_err());
+        let config = SearchConfig { guess_p: GuessP::Weights(vec![1.0; 3]), ..Default::default() };
+        assert_eq!(config.validate(), Err(ConfigError::GuessPLength { expected: 2, found: 3 }));
+        let config = SearchConfig { guess_p: GuessP::Weights(vec![0.0; 2]), ..Default::default() };
+        assert!(config.validate().is_err());
+        let config = SearchConfig { time_limit: Some(0.0), ..Default::default() };
+        assert_eq!(config.validate(), Err(ConfigError::BadTimeLimit(0.0)));
+        let config = SearchConfig { s: vec![3, 3, 3, 3, 3], ..Default::default() };
+        assert_eq!(config.validate(), Err(ConfigError::UnsupportedC(5)));
+        let config = SearchConfig { s: vec![3, 1], ..Default::default() };
+        assert_eq!(config.validate(), Err(ConfigError::BadS(vec![3, 1])));
+        let annealing = AnnealingConfig { final_temperature: 3.0, ..Default::default() };
+        let config = SearchConfig { annealing, ..Default::default() };
+        assert_eq!(config.validate(), Err(ConfigError::BadTemperatures { initial: 2.0, last: 3.0 }));
+        let memory = MemoryConfig { max_graphs: Some(0), ..Default::default() };
+        assert_eq!(SearchConfig { memory, ..Default::default() }.validate(), Err(ConfigError::Zero("MAX_GRAPHS")));
+        let memory = MemoryConfig { max_megabytes: Some(-1.0), ..Default::default() };
+        assert_eq!(SearchConfig { memory, ..Default::default() }.validate(), Err(ConfigError::BadMemory(-1.0)));
+        let selection = SelectionConfig { explore_decay: 0.0, ..Default::default() };
+        assert_eq!(SearchConfig { selection, ..Default::default() }.validate(), Err(ConfigError::BadExploreDecay(0.0)));
+        let selection = SelectionConfig { softmax_temperature: -1.0, ..Default::default() };
+        assert_eq!(SearchConfig { selection, ..Default::default() }.validate(), Err(ConfigError::BadSelectionTemperature(-1.0)));
+        let selection = SelectionConfig { prior_temperature: 0.0, ..Default::default() };
+        assert_eq!(SearchConfig { selection, ..Default::default() }.validate(), Err(ConfigError::BadSelectionTemperature(0.0)));
+        let selection = SelectionConfig { discount: 1.5, ..Default::default() };
+        assert_eq!(SearchConfig { selection, ..Default::default() }.validate(), Err(ConfigError::BadDiscount(1.5)));
+        let length = LengthConfig { moves: Some(0), ..Default::default() };
+        assert_eq!(SearchConfig { length, ..Default::default() }.validate(), Err(ConfigError::Zero("MOVES")));
+        let length = LengthConfig { ratio: f64::INFINITY, ..Default::default() };
+        assert_eq!(SearchConfig { length, ..Default::default() }.validate(), Err(ConfigError::BadLengthFactor(f64::INFINITY)));
+    }
+
+    #[test]
+    fn toml_round_trip() {
+        let config = SearchConfig {
+            s: vec![3, 3, 3],
+            n: 16,
+            guess_p: GuessP::Weights(vec![0.2, 0.3, 0.5]),
+            seed: Some(7),
+            time_limit: Some(60.0),
+            strategy: Strategy::Tabu,
+            tabu: TabuConfig { tenure: 7, aspiration: false },
+            annealing: AnnealingConfig { schedule: Schedule::Adaptive, proposal: Proposal::Slope, ..Default::default() },
+            memory: MemoryConfig { max_graphs: Some(1_000_000), max_megabytes: Some(512.0), eviction: Eviction::Lru },
+            selection: SelectionConfig {
+                policy: Policy::Puct,
+                explore_decay: 0.9,
+                softmax_temperature: 0.5,
+                prior_temperature: 2.0,
+                backup: Backup::Discounted,
+                discount: 0.5
+            },
+            length: LengthConfig { schedule: Length::Luby, moves: Some(8), max_moves: Some(64), ..Default::default() },
+            ..Default::default()
+        };
+        assert_eq!(toml::from_str(&config.to_toml()), Ok(config))
+    }
+
+    #[test]
+    fn partial_files() {
+        let config: SearchConfig = toml::from_str("s = [4, 5]\nn = 24\n").unwrap();
+        assert_eq!(config, SearchConfig { s: vec![4, 5], n: 24, ..Default::default() });
+        assert_eq!(config.guess_p(), vec![0.5, 0.5]);
+        let config: SearchConfig = serde_json::from_str(r#"{"s": [3, 3, 3], "n": 16}"#).unwrap();
+        assert_eq!(config.guess_p().len(), 3);
+        assert!(toml::from_str::<SearchConfig>("s = [3, 3]\nN = 5\n").is_err());
+        assert!(toml::from_str::<SearchConfig>("s = \"3,3\"\n").is_err());
+        let config: SearchConfig = toml::from_str("guess_p = \"auto\"\n").unwrap();
+        assert_eq!(config.guess_p, GuessP::Auto);
+        let config: SearchConfig = toml::from_str("guess_p = [1, 3]\n").unwrap();
+        assert_eq!(config.guess_p, GuessP::Weights(vec![1.0, 3.0]));
+        assert!(toml::from_str::<SearchConfig>("guess_p = \"best\"\n").is_err());
+        let config: SearchConfig = toml::from_str("strategy = \"tabu\"\n[tabu]\ntenure = 5\n").unwrap();
+        assert_eq!((config.strategy, config.tabu), (Strategy::Tabu, TabuConfig { tenure: 5, aspiration: true }));
+        assert!(toml::from_str::<SearchConfig>("strategy = \"greedy\"\n").is_err());
+        assert_eq!("linear".parse(), Ok(Schedule::Linear));
+        assert!("cubic".parse::<Schedule>().is_err());
+    }
+
+    #[test]
+    fn parse_guess_p() {
+        assert_eq!("auto".parse(), Ok(GuessP::Auto));
+        assert_eq!("uniform".parse(), Ok(GuessP::Uniform));
+        assert_eq!("0.25, 0.75".parse(), Ok(GuessP::Weights(vec![0.25, 0.75])));
+        assert!("0.25;0.75".parse::<GuessP>().is_err());
+    }
+
+    #[test]
+    fn auto_guess_p_is_optimal() {
+        for (s, n) in [(vec![3, 3], 5), (vec![3, 3, 3], 16), (vec![3, 5], 13), (vec![4, 6], 35), (vec![3, 9], 35), (vec![2, 5], 7), (vec![4, 30], 128), (vec![5, 40], 256)] {
+            let p = GuessP::Auto.resolve(&s, n);
+            assert!(p.iter().all(|p| p.is_finite()), "{s:?} {n} {p:?}");
+            assert!((p.iter().sum::<f64>() - 1.0).abs() < 1e-9);
+            let cost = expected_cliques(&p, &s, n);
+            assert!(cost <= expected_cliques(&uniform(s.len()), &s, n) * (1.0 + 1e-9), "{s:?} {n} {p:?}");
+            /* no small step along the simplex improves on p */
+            for (i, j) in (0..s.len()).flat_map(|i| (0..s.len()).map(move |j| (i, j))) {
+                if i == j || p[i] < 1e-3 { continue }
+                let mut q = p.clone();
+                q[i] -= 1e-3;
+                q[j] += 1e-3;
+                assert!(cost <= expected_cliques(&q, &s, n) * (1.0 + 1e-9), "{s:?} {n} {p:?}");
+            }
+        }
+        assert_eq!(GuessP::Auto.resolve(&[3, 3], 5), vec![0.5, 0.5]);
+        assert_eq!(GuessP::Auto.resolve(&[3, 9], 8), vec![0.0, 1.0]);
+    }
+
+    #[test]
+    fn binomials_beyond_iyy() {
+        assert_eq!(binomial(5, 2), 10.0);
+        assert_eq!(binomial(3, 9), 0.0);
+        assert_eq!(binomial(40, 20), crate::prelude::choose(40, 20) as f64);
+        let ratio = binomial(256, 40) / 1.0492125e47;
+        assert!((ratio - 1.0).abs() < 1e-4, "{}", binomial(256, 40));
+    }
+
+    #[test]
+    fn example_experiment() {
+        let config = SearchConfig::load(Path::new("experiments/r3_3_3_16.toml")).unwrap();
+        assert_eq!(config.validate(), Ok(()));
+        assert_eq!((config.s, config.n), (vec![3, 3, 3], 16));
+        assert!(SearchConfig::load(Path::new("experiments/r3_3_3_16.yaml")).is_err())
+    }
+}
@@ -0,0 +1,62 @@
+use crate::{colored_graph::neighborhood::*, config::{ConfigError, MIN_N, MAX_N}};
+
+/* a job generic over the colors and the neighborhood width, run once C and N are known at runtime */
+pub trait Dispatch {
+    type Output;
+    fn run<T: Neighborhood, const C: usize>(self, n: usize) -> Self::Output;
+}
+
+pub fn dispatch<D: Dispatch>(c: usize, n: usize, job: D) -> Result<D::Output, ConfigError> {
+    match c {
+        2 => dispatch_n::<2, D>(n, job),
+        3 => dispatch_n::<3, D>(n, job),
+        4 => dispatch_n::<4, D>(n, job),
+        c => Err(ConfigError::UnsupportedC(c))
+    }
+}
+
+/* the narrowest neighborhood that holds n vertices, so the job is built once per width rather than per N */
+fn dispatch_n<const C: usize, D: Dispatch>(n: usize, job: D) -> Result<D::Output, ConfigError> {
+    match n {
+        MIN_N..=8 => Ok(job.run::<U8N, C>(n)),
+        9..=16 => Ok(job.run::<U16N, C>(n)),
+        17..=32 => Ok(job.run::<U32N, C>(n)),
+        33..=64 => Ok(job.run::<U64N, C>(n)),
+        65..=128 => Ok(job.run::<U128N, C>(n)),
+        129..=192 => Ok(job.run::<U192N, C>(n)),
+        193..=MAX_N => Ok(job.run::<U256N, C>(n)),
+        n => Err(ConfigError::UnsupportedN(n))
+    }
+}
+
+#[cfg(test)]
+mod dispatch_tests {
+    use super::*;
+    use crate::config::{MIN_C, MAX_C};
+
+    struct Dimensions;
+
+    impl Dispatch for Dimensions {
+        type Output = (usize, usize, usize);
+        fn run<T: Neighborhood, const C: usize>(self, n: usize) -> Self::Output {
+            (C, n, T::BITS)
+        }
+    }
+
+    #[test]
+    fn every_supported_n() {
+        let widths = [8, 16, 32, 64, 128, 192, 256];
+        for c in MIN_C..=MAX_C {
+            for n in MIN_N..=MAX_N {
+                let (dispatched_c, dispatched_n, bits) = dispatch(c, n, Dimensions).unwrap();
+                assert_eq!((dispatched_c, dispatched_n), (c, n));
+                /* the narrowest width that fits */
+                let width = widths.iter().position(|&width| width == bits).unwrap();
+                assert!(n <= bits && (width == 0 || widths[width - 1] < n))
+            }
+            assert_eq!(dispatch(c, MIN_N - 1, Dimensions), Err(ConfigError::UnsupportedN(MIN_N - 1)));
+            assert_eq!(dispatch(c, MAX_N + 1, Dimensions), Err(ConfigError::UnsupportedN(MAX_N + 1)))
+        }
+        assert_eq!(dispatch(MAX_C + 1, MIN_N, Dimensions), Err(ConfigError::UnsupportedC(MAX_C + 1)))
+    }
+}
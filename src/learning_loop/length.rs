@@ -0,0 +1,83 @@
+use crate::config::{Length, LengthConfig};
+
+/* how many moves the episodes of each epoch take */
+#[derive(Clone, Debug)]
+pub struct EpisodeLength {
+    schedule: Length,
+    moves: usize,
+    increment: usize,
+    ratio: f64,
+    slack: f64,
+    max_moves: usize
+}
+
+impl EpisodeLength {
+    /* for colorings of `edges` edges */
+    pub fn new(config: &LengthConfig, edges: usize) -> Self {
+        EpisodeLength {
+            schedule: config.schedule,
+            moves: config.moves.unwrap_or(edges / 4),
+            increment: config.increment,
+            ratio: config.ratio,
+            slack: config.slack,
+            max_moves: config.max_moves.unwrap_or(usize::MAX)
+        }
+    }
+
+    /* `improved_at` is the depth of the latest improvement, if any */
+    pub fn moves(&self, epoch: usize, improved_at: Option<usize>) -> usize {
+        let moves = match self.schedule {
+            Length::Linear => self.moves.saturating_add(self.increment.saturating_mul(epoch)),
+            Length::Constant => self.moves,
+            Length::Geometric => (self.moves as f64 * self.ratio.powi(epoch as i32 - 1)).round() as usize,
+            Length::Luby => self.moves.saturating_mul(luby(epoch)),
+            Length::Adaptive => improved_at
+                .map_or(0, |depth| (self.slack * depth as f64).ceil() as usize)
+                .max(self.moves)
+        };
+        moves.clamp(1, self.max_moves)
+    }
+}
+
+/* 1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8, ... counting from 1 */
+fn luby(mut i: usize) -> usize {
+    loop {
+        let k = usize::BITS - i.leading_zeros();
+        /* i = 2^k - 1 ends a run */
+        if i == (1 << k) - 1 { return 1 << (k - 1) }
+        i -= (1 << (k - 1)) - 1
+    }
+}
+
+#[cfg(test)]
+mod length_tests {
+    use super::*;
+
+    fn length(schedule: Length) -> EpisodeLength {
+        EpisodeLength::new(&LengthConfig { schedule, ..Default::default() }, 136)
+    }
+
+    #[test]
+    fn default_grows_by_one() {
+        assert_eq!((1..=3).map(|epoch| length(Length::Linear).moves(epoch, Some(5))).collect::<Vec<_>>(), [35, 36, 37]);
+    }
+
+    #[test]
+    fn schedules() {
+        assert_eq!(length(Length::Constant).moves(9, None), 34);
+        assert_eq!(length(Length::Geometric).moves(1, None), 34);
+        assert_eq!(length(Length::Geometric).moves(3, None), (34.0 * 1.21f64).round() as usize);
+        assert_eq!((1..=8).map(|epoch| length(Length::Luby).moves(epoch, None) / 34).collect::<Vec<_>>(), [1, 1, 2, 1, 1, 2, 4, 1]);
+        assert_eq!(length(Length::Adaptive).moves(4, None), 34);
+        assert_eq!(length(Length::Adaptive).moves(4, Some(10)), 34);
+        assert_eq!(length(Length::Adaptive).moves(4, Some(30)), 60);
+        let capped = LengthConfig { moves: Some(0), max_moves: Some(50), ..Default::default() };
+        assert_eq!(EpisodeLength::new(&capped, 136).moves(100, None), 50);
+        assert_eq!(EpisodeLength::new(&capped, 136).moves(0, None), 1);
+    }
+
+    #[test]
+    fn luby_sequence() {
+        assert_eq!((1..=15).map(luby).collect::<Vec<_>>(), [1, 1, 2, 1, 1, 2, 4, 1, 1, 2, 1, 1, 2, 4, 8]);
+    }
+}
@@ -1,65 +1,182 @@
+pub mod length;
 mod search_map;
+pub mod selection;
 
-use rand::distributions::WeightedIndex;
-use crate::{prelude::*, colored_graph::neighborhood::Neighborhood};
-use crate::{action_matrix::*, colored_graph::*};
-use self::search_map::*;
+use std::{thread, sync::{Mutex, atomic::{AtomicBool, AtomicU64, Ordering}}};
 
-pub fn play_episode<T: Neighborhood, const C: usize, const N: usize, const E: usize>
-(g_map: &mut GraphMap<T, C, N, E>, score_keeper: &mut ScoreKeeper<T, C, N, E>, n_moves: usize)
--> Result<(), ScoreUpdate>
+use rand::{Rng, RngCore, SeedableRng, rngs::StdRng};
+
+use crate::colored_graph::{canonical::Symmetry, neighborhood::Neighborhood};
+use crate::{action_matrix::*, config::*, strategy::*};
+use crate::{prelude::{Uzz, choose_two}, checkpoint::Visit, colored_graph::display::Graph6Error};
+use self::{length::EpisodeLength, search_map::*, selection::{Selection, SelectionPolicy}};
+
+pub fn play_episode<T: Neighborhood, const C: usize>(
+    g_map: &GraphMap<T, C>,
+    score_keeper: &SharedKeeper<T, C>,
+    n_moves: usize,
+    policy: &dyn SelectionPolicy,
+    rng: &mut dyn RngCore
+) -> Result<(), SearchError>
 {
-    let mut rng = rand::thread_rng();
-    let mut chosen_root = score_keeper.random_root(&mut rng).clone();
+    let mut chosen_root = score_keeper.random_root(rng);
     let mut action_matrix = chosen_root.clone();
     let mut actions_taken = vec!();
     for _ in 0..n_moves {
-        match g_map.next_action(&mut action_matrix) {
+        match g_map.next_action(&mut action_matrix, policy, rng) {
             Some(action) => actions_taken.push(action),
-            None => todo!("refactor the return type")
+            None => return Err(SearchError::NoAction)
         }
     }
 
-    g_map.update_counts(score_keeper,&mut chosen_root, actions_taken)
+    g_map.update_counts(score_keeper, &mut chosen_root, actions_taken);
+    Ok(())
 }
 
-pub fn play_epoch<T: Neighborhood, const C: usize, const N: usize, const E: usize>(
-    g_map: &mut GraphMap<T, C, N, E>,
-    score_keeper: &mut ScoreKeeper<T, C, N, E>,
-    n_moves: usize,
-    n_episodes: Uzz
-) -> Option<ScoreUpdate>
+/* what the episodes of one epoch have in common */
+pub struct Epoch<'a> {
+    pub epoch: usize,
+    pub n_moves: usize,
+    pub policy: &'a dyn SelectionPolicy
+}
+
+/* `threads` workers take episodes from a common counter; a single worker runs on this thread with `rng`,
+   several seed their own from it. All stop at a witness or the first error. */
+pub fn play_epoch<T: Neighborhood, const C: usize>(
+    g_map: &GraphMap<T, C>,
+    score_keeper: &SharedKeeper<T, C>,
+    &Epoch { epoch, n_moves, policy }: &Epoch,
+    budget: &Budget,
+    threads: usize,
+    rng: &mut dyn RngCore
+) -> Result<Uzz, SearchError>
 {
-    for i in 1..(n_episodes+1) {
-        if i % (10 * EPISODES) == 0 { println!("== EPISODE == {i}") }
-        if let Err(ScoreUpdate::Done) = play_episode(g_map, score_keeper, n_moves) {
-            return Some(ScoreUpdate::Done)
+    let episodes = budget.episode_range(epoch);
+    let next_episode = AtomicU64::new(episodes.start + 1);
+    let played = AtomicU64::new(episodes.start);
+    let failure = Mutex::new(None);
+    let failed = AtomicBool::new(false);
+    /* a single worker on an uncapped map reports as it always did */
+    let sizes = threads > 1 || g_map.capped();
+    let worker = |rng: &mut dyn RngCore| loop {
+        let i = next_episode.fetch_add(1, Ordering::Relaxed);
+        if i > episodes.end || score_keeper.best_count() == 0 || failed.load(Ordering::Relaxed) { break }
+        if i.is_multiple_of(10_000) {
+            if sizes { println!("== EPISODE == {i} ({})", g_map.size()) }
+            else { println!("== EPISODE == {i}") }
+        }
+        if budget.expired() { break }
+        score_keeper.checkpoint(g_map, (epoch, played.load(Ordering::Relaxed)), false);
+        if let Err(error) = play_episode(g_map, score_keeper, n_moves, policy, rng) {
+            failure.lock().unwrap().get_or_insert(error);
+            failed.store(true, Ordering::Relaxed);
+            break
         }
+        played.fetch_add(1, Ordering::Relaxed);
+    };
+
+    if threads == 1 { worker(rng) }
+    else {
+        let worker = &worker;
+        thread::scope(|scope| {
+            for _ in 0..threads {
+                let seed = rng.gen();
+                scope.spawn(move || worker(&mut StdRng::seed_from_u64(seed)));
+            }
+        })
+    }
+    match failure.into_inner().unwrap() {
+        Some(error) => Err(error),
+        None => Ok(played.into_inner())
     }
-    None
 }
 
-pub fn play_epochs<T: Neighborhood, const C: usize, const N: usize, const E: usize>
-(g_map: &mut GraphMap<T, C, N, E>, score_keeper: &mut ScoreKeeper<T, C, N, E>)
+/* writes a last checkpoint however the search stops; the steps are the episodes played */
+pub fn play_epochs<T: Neighborhood, const C: usize>(
+    g_map: &GraphMap<T, C>,
+    score_keeper: &SharedKeeper<T, C>,
+    selection: &Selection,
+    length: &EpisodeLength,
+    budget: &Budget,
+    threads: usize,
+    rng: &mut dyn RngCore
+) -> Result<SearchStats, SearchError>
 {
-    for epoch in 1..(EPOCHS+1) {
-        println!("==== EPOCH ==== {epoch}");
-        if let Some(ScoreUpdate::Done) = play_epoch::<T, C, N, E>(g_map, score_keeper, E/4 + epoch, EPISODES) {
-            println!("R{S:?} > {N}");
-            return
+    let mut at = (budget.first_epoch, budget.first_episode);
+    let mut steps = 0;
+    for epoch in budget.epoch_range() {
+        score_keeper.epoch(epoch);
+        let policy = selection.at_epoch(epoch);
+        let plan = Epoch { epoch, n_moves: length.moves(epoch, score_keeper.last_improvement()), policy: &policy };
+        let played = match play_epoch::<T, C>(g_map, score_keeper, &plan, budget, threads, rng) {
+            Ok(played) => played,
+            Err(error) => {
+                score_keeper.checkpoint(g_map, at, true);
+                return Err(error)
+            }
+        };
+        steps += played - budget.episode_range(epoch).start;
+        at = (epoch, played);
+        if threads > 1 || g_map.capped() { println!("\rgraph map: {}", g_map.size()) }
+        if score_keeper.best_count() == 0 { break }
+        if budget.timed_out() || (epoch < budget.epochs && budget.expired()) {
+            println!("==== TIME LIMIT ====");
+            break
         }
     }
+    score_keeper.checkpoint(g_map, at, true);
+    Ok(SearchStats::new(budget, at.0, steps))
 }
 
-pub fn search<T: Neighborhood, const C: usize, const N: usize, const E: usize>()
-{
-    let mut rng = rand::thread_rng();
-    let dist = WeightedIndex::new(&GUESS_P)
-        .unwrap();
-    let graph = ColoredGraph::<T, C, N>::random(&mut rng, &dist);
-    let actions = ActionMatrix::from(graph);
-    
-    let mut score_keeper = ScoreKeeper::from(actions);
-    let mut g_map = GraphMap::default();
-    play_epochs::<T, C, N, E>(&mut g_map, &mut score_keeper);
+/* the tabular MCTS: episodes replay `GraphMap`'s choices from the tied best colorings */
+pub struct Mcts<T: Neighborhood, const C: usize> {
+    g_map: GraphMap<T, C>,
+    selection: Selection,
+    length: EpisodeLength,
+    max_roots: usize,
+    threads: usize,
+    symmetry: Symmetry,
+    /* tied minima from a checkpoint, which replace the start coloring */
+    roots: Option<Vec<ActionMatrix<T, C>>>
+}
+
+impl<T: Neighborhood, const C: usize>
+Mcts<T, C> {
+    pub fn new(config: &SearchConfig) -> Self {
+        let shards = if config.threads == 1 { 1 } else { 8 * config.threads };
+        let symmetry = Symmetry::new(config.canonical, &config.s, config.permute_colors);
+        Mcts {
+            g_map: GraphMap::new(config.n, shards, &config.memory, &config.selection, symmetry.clone()),
+            selection: Selection::new(config.explore, &config.selection),
+            length: EpisodeLength::new(&config.length, choose_two(config.n)),
+            max_roots: config.roots,
+            threads: config.threads,
+            symmetry,
+            roots: None
+        }
+    }
+}
+
+impl<T: Neighborhood, const C: usize>
+SearchStrategy<T, C> for Mcts<T, C> {
+    fn search(
+        &mut self,
+        start: ActionMatrix<T, C>,
+        budget: &Budget,
+        observer: &mut dyn Observer<T, C>,
+        rng: &mut dyn RngCore
+    ) -> Result<SearchOutcome<ActionMatrix<T, C>>, SearchError> {
+        let mut score_keeper = match self.roots.take() {
+            Some(roots) => ScoreKeeper::from_roots(roots, self.max_roots, self.symmetry.clone()),
+            None => ScoreKeeper::new(start, self.max_roots, self.symmetry.clone())
+        };
+        let shared = SharedKeeper::new(&mut score_keeper, observer);
+        let stats = play_epochs(&self.g_map, &shared, &self.selection, &self.length, budget, self.threads, rng)?;
+        Ok(SearchOutcome::new(score_keeper.best().clone(), budget, stats))
+    }
+
+    fn restore(&mut self, roots: Vec<ActionMatrix<T, C>>, visits: &[Visit]) -> Result<(), Graph6Error> {
+        self.roots = Some(roots);
+        self.g_map.restore(visits)
+    }
 }
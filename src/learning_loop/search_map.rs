@@ -1,34 +1,147 @@
-use crate::learning_loop::neighborhood::Neighborhood;
-use crate::prelude::{Iyy, Uzz, S, ROOTS, EXPLORE};
-use crate::{colored_graph::*, action_matrix::*};
+use crate::colored_graph::{canonical::{Relabeling, Symmetry}, neighborhood::Neighborhood};
+use crate::prelude::{Iyy, Uzz, edge_to_pos, pos_to_edge};
+use crate::{colored_graph::*, action_matrix::*, strategy::Observer};
+use crate::checkpoint::{SearchState, Visit, VisitAction};
+use crate::colored_graph::display::Graph6Error;
+use crate::config::{Backup, Eviction, MemoryConfig, SelectionConfig};
+use super::selection::{Candidate, SelectionPolicy, select};
 
-use std::collections::HashMap;
-use rand::{rngs::ThreadRng, seq::SliceRandom};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet, hash_map::DefaultHasher};
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex, MutexGuard, atomic::{AtomicI64, AtomicU64, AtomicUsize, Ordering}};
+use std::time::{Duration, Instant};
+use rand::{Rng, RngCore, seq::SliceRandom};
 
 
 
-pub struct ScoreKeeper<T: Neighborhood, const C: usize, const N: usize, const E: usize> {
-    roots: Vec<ActionMatrix<T, C, N, E>>,
-    best_count: Iyy,
-    name: String
+pub struct ScoreKeeper<T: Neighborhood, const C: usize> {
+    /* shared, so that an episode takes its root out of the lock before cloning it */
+    roots: Vec<Arc<ActionMatrix<T, C>>>,
+    /* the canonical forms of the roots under `symmetry`, so that relabeled ties count once */
+    keys: HashSet<ColoredGraph<T, C>>,
+    symmetry: Symmetry,
+    max_roots: usize,
+    best_count: Iyy
 }
 
-impl<T: Neighborhood, const C: usize, const N: usize, const E: usize>
-From<ActionMatrix<T, C, N, E>> for ScoreKeeper<T, C, N, E> {
-    fn from(actions: ActionMatrix<T, C, N, E>) -> Self {
-        let count = actions.total();
-        let name = format!("r{S:?}_{N}");
-        ScoreKeeper { roots: vec![actions], best_count: count, name }
+impl<T: Neighborhood, const C: usize>
+ScoreKeeper<T, C> {
+    pub fn new(actions: ActionMatrix<T, C>, max_roots: usize, symmetry: Symmetry) -> Self {
+        ScoreKeeper::from_roots(vec![actions], max_roots, symmetry)
+    }
+
+    /* tied minima, as saved in a checkpoint */
+    pub fn from_roots(roots: Vec<ActionMatrix<T, C>>, max_roots: usize, symmetry: Symmetry) -> Self {
+        let count = roots[0].total();
+        let keys = roots.iter().map(|root| symmetry.canonical(root.graph()).0).collect();
+        ScoreKeeper {
+            roots: roots.into_iter().map(Arc::new).collect(),
+            keys,
+            symmetry,
+            max_roots,
+            best_count: count
+        }
     }
-}
 
-impl<T: Neighborhood, const C: usize, const N: usize, const E: usize>
-ScoreKeeper<T, C, N, E> {
-    pub fn random_root(&self, rng: &mut ThreadRng) -> &ActionMatrix<T, C, N, E> { 
+    pub fn best(&self) -> &ActionMatrix<T, C> {
+        &self.roots[0]
+    }
+
+    pub fn roots(&self) -> &[Arc<ActionMatrix<T, C>>] {
+        &self.roots
+    }
+
+    pub fn random_root<R: Rng + ?Sized>(&self, rng: &mut R) -> &Arc<ActionMatrix<T, C>> { 
         self.roots.choose(rng).unwrap()
     }
 }
 
+/* like `GraphMap::key`, borrowed when there is no symmetry to apply */
+fn root_key<'g, T: Neighborhood, const C: usize>(symmetry: &Symmetry, graph: &'g ColoredGraph<T, C>)
+-> Cow<'g, ColoredGraph<T, C>>
+{
+    if symmetry.is_trivial() { return Cow::Borrowed(graph) }
+    Cow::Owned(symmetry.canonical(graph).0)
+}
+
+/* the score keeper and observer behind one lock, with the best count readable without it */
+pub struct SharedKeeper<'a, T: Neighborhood, const C: usize> {
+    keeper: Mutex<(&'a mut ScoreKeeper<T, C>, &'a mut dyn Observer<T, C>)>,
+    /* the keeper's, so that episodes key their colorings before taking the lock */
+    symmetry: Symmetry,
+    best_count: AtomicI64,
+    /* moves into its episode of the latest improvement, 0 before any */
+    improved_at: AtomicUsize,
+    started: Instant,
+    /* milliseconds after `started` before which no checkpoint is due, so episodes skip the lock until then */
+    checkpoint_at: AtomicU64
+}
+
+impl<'a, T: Neighborhood, const C: usize>
+SharedKeeper<'a, T, C> {
+    pub fn new(keeper: &'a mut ScoreKeeper<T, C>, observer: &'a mut dyn Observer<T, C>) -> Self {
+        let best_count = AtomicI64::new(keeper.best_count);
+        let symmetry = keeper.symmetry.clone();
+        SharedKeeper {
+            keeper: Mutex::new((keeper, observer)),
+            symmetry,
+            best_count,
+            improved_at: AtomicUsize::new(0),
+            started: Instant::now(),
+            checkpoint_at: AtomicU64::new(0)
+        }
+    }
+
+    pub fn best_count(&self) -> Iyy {
+        self.best_count.load(Ordering::Relaxed)
+    }
+
+    pub fn last_improvement(&self) -> Option<usize> {
+        Some(self.improved_at.load(Ordering::Relaxed)).filter(|&depth| depth > 0)
+    }
+
+    pub fn random_root<R: Rng + ?Sized>(&self, rng: &mut R) -> ActionMatrix<T, C> {
+        let root = self.keeper.lock().unwrap().0.random_root(rng).clone();
+        ActionMatrix::clone(&root)
+    }
+
+    /* the keys eviction must keep */
+    pub fn root_graphs(&self) -> HashSet<ColoredGraph<T, C>> {
+        self.keeper.lock().unwrap().0.keys.clone()
+    }
+
+    pub fn epoch(&self, epoch: usize) {
+        self.keeper.lock().unwrap().1.epoch(epoch)
+    }
+
+    /* `episode` episodes of `epoch` are done; the observer decides whether this is a good time */
+    pub fn checkpoint(&self, g_map: &GraphMap<T, C>, (epoch, episode): (usize, Uzz), finished: bool) {
+        let millis = |elapsed: Duration| u64::try_from(elapsed.as_millis()).unwrap_or(u64::MAX);
+        if !finished && millis(self.started.elapsed()) < self.checkpoint_at.load(Ordering::Relaxed) { return }
+        let mut keeper = self.keeper.lock().unwrap();
+        let (keeper, observer) = &mut *keeper;
+        if let Some(with_visits) = observer.checkpoint_due(finished) {
+            let visits = if with_visits { g_map.visits() } else { vec![] };
+            observer.checkpoint(SearchState::new(epoch, episode, keeper.roots(), visits))
+        }
+        let checkpoint_at = observer.next_checkpoint()
+            .map_or(u64::MAX, |wait| millis(self.started.elapsed()).saturating_add(millis(wait)));
+        self.checkpoint_at.store(checkpoint_at, Ordering::Relaxed)
+    }
+
+    pub fn update(&self, actions: &ActionMatrix<T, C>) -> ScoreUpdate {
+        if actions.total() > self.best_count.load(Ordering::Relaxed) { return ScoreUpdate::Worse }
+        let key = root_key(&self.symmetry, actions.graph());
+        let mut keeper = self.keeper.lock().unwrap();
+        let (keeper, observer) = &mut *keeper;
+        let update = keeper.update_keyed(actions, key, *observer);
+        self.best_count.store(keeper.best_count, Ordering::Relaxed);
+        update
+    }
+}
+
 pub enum ScoreUpdate {
     Done,
     Better,
@@ -37,23 +150,41 @@ pub enum ScoreUpdate {
     Worse
 }
 
-impl<T: Neighborhood, const C: usize, const N: usize, const E: usize>
-ScoreKeeper<T, C, N, E> {
+impl<T: Neighborhood, const C: usize>
+ScoreKeeper<T, C> {
+    #[must_use]
+    pub fn update(
+        &mut self,
+        actions: &ActionMatrix<T, C>,
+        observer: &mut dyn Observer<T, C>
+    ) -> ScoreUpdate {
+        let key = root_key(&self.symmetry, actions.graph());
+        self.update_keyed(actions, key, observer)
+    }
+
+    /* `update` with the key of `actions` already at hand */
     #[must_use]
-    pub fn update(&mut self, actions: &ActionMatrix<T, C, N, E>) -> ScoreUpdate {
+    fn update_keyed(
+        &mut self,
+        actions: &ActionMatrix<T, C>,
+        key: Cow<'_, ColoredGraph<T, C>>,
+        observer: &mut dyn Observer<T, C>
+    ) -> ScoreUpdate {
         let count = actions.total();
         match self.best_count.cmp(&count) {
             std::cmp::Ordering::Less => ScoreUpdate::Worse,
             std::cmp::Ordering::Equal => {
-                if !self.roots.contains(actions) {
-                    match self.roots.len().cmp(&ROOTS) {
+                if !self.keys.contains(&*key) {
+                    match self.roots.len().cmp(&self.max_roots) {
                         std::cmp::Ordering::Less => {
-                            self.roots.push(actions.clone());
+                            self.roots.push(Arc::new(actions.clone()));
+                            self.keys.insert(key.into_owned());
                             print!("\r{} minima... ", self.roots.len())
                         }
                         std::cmp::Ordering::Equal => {
-                            self.roots.push(actions.clone());
-                            println!("\r{ROOTS}+ minima... ")
+                            self.roots.push(Arc::new(actions.clone()));
+                            self.keys.insert(key.into_owned());
+                            println!("\r{}+ minima... ", self.max_roots)
                         }
                         std::cmp::Ordering::Greater => {}
                     }
@@ -69,25 +200,12 @@ ScoreKeeper<T, C, N, E> {
                 }
             },
             std::cmp::Ordering::Greater => {
-                self.roots = vec![actions.clone()];
+                self.roots = vec![Arc::new(actions.clone())];
+                self.keys = HashSet::from([key.into_owned()]);
                 self.best_count = count;
-                println!("score improved to {count} by");
-                if N <= 10 {
-                    self.roots[0].graph().show_neighborhoods();
-                }
-                if N <= 25 {
-                    self.roots[0].graph().show_matrix();
-                    println!();
-                }
-                println!("{:?}", self.roots[0].graph().graph6s());
-                let docs = actions
-                    .graph()
-                    .svg(self.name.clone());
-                docs.render();
-                
+                observer.improved(actions);
                 print!("\r{} minimum... ", self.roots.len());
                 if count == 0 {
-                    println!("==== DONE ====\nCheck out plots/{}*.svg 😊", self.name);
                     ScoreUpdate::Done
                 }
                 else {
@@ -98,151 +216,576 @@ ScoreKeeper<T, C, N, E> {
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ActionStats {
+    /* the slope when first taken */
+    q_ga: Iyy,
+    n_ga: Uzz,
+    /* backed up from the episodes taking the action */
+    value: f64
+}
+
 #[derive(Default)]
 pub struct ActionMap {
-    actions: HashMap<Action, (Iyy, Uzz)>
+    actions: HashMap<Action, ActionStats>
 }
 
 #[derive(Default)]
 pub struct GraphData {
     n_visits: Uzz,
+    /* the `GraphMap` clock at the first and the latest visit */
+    first_visit: Uzz,
+    last_visit: Uzz,
     action_map: ActionMap
 }
 
 impl GraphData {
-    pub fn record(&mut self, action: Action, q_ga: Option<Iyy>) {
+    /* true when `action` is new here; `value` is backed up into its running mean or max */
+    pub fn record(&mut self, action: Action, q_ga: Option<Iyy>, value: f64, backup: Backup) -> bool {
         self.n_visits += 1;
         let res = self.action_map.actions.get_mut(&action);
         match res {
-            Some((_, n_ga)) => *n_ga += 1,
-            None => {self.action_map.actions.insert(action, (q_ga.unwrap(), 1));}
+            Some(stats) => {
+                stats.n_ga += 1;
+                match backup {
+                    Backup::Slope => {},
+                    Backup::Mean | Backup::Discounted => stats.value += (value - stats.value) / stats.n_ga as f64,
+                    Backup::Max => stats.value = stats.value.max(value)
+                }
+                false
+            },
+            None => {
+                self.action_map.actions.insert(action, ActionStats { q_ga: q_ga.unwrap(), n_ga: 1, value });
+                true
+            }
         }
     }
-    
-    pub fn default_nu(&self) -> f64 {
-        EXPLORE * (self.n_visits as f64).sqrt()
+
+    /* the actions taken here, least first, so that seeded searches don't depend on the map's iteration order */
+    pub fn visited(&self) -> Vec<Candidate> {
+        let mut visited: Vec<_> = self.action_map.actions
+            .iter()
+            .map(|(&action, stats)| Candidate { action, slope: stats.q_ga, value: stats.value, n_ga: stats.n_ga })
+            .collect();
+        visited.sort_unstable_by_key(|candidate| candidate.action);
+        visited
     }
-    
-    fn mu(&self, action: &Action) -> Option<f64> {
-        let value = self.action_map.actions.get(action);
-        let (q_ga, n_ga) = match value {
-            Some(&value) => value,
-            None => return None
+}
+
+/* the return of each move of an episode that went through `totals` to `last`: the best improvement
+   reached from there, or the discounted sum of the slopes from there */
+fn returns(totals: &[Iyy], last: Iyy, backup: Backup, discount: f64) -> Vec<f64> {
+    let mut returns = vec![0.0; totals.len()];
+    let (mut best, mut discounted, mut next) = (last, 0.0, last);
+    for (i, &total) in totals.iter().enumerate().rev() {
+        discounted = (total - next) as f64 + discount * discounted;
+        returns[i] = match backup {
+            Backup::Discounted => discounted,
+            _ => (total - best) as f64
         };
-        
-        Some(
-            q_ga as f64 + 
-            EXPLORE * (self.n_visits as f64).sqrt()
-            / ((1 + n_ga) as f64)
-        )
-    }
-
-    pub fn visited_argmax(&self, /* seen_edges: &[bool; E]*/ ) -> Option<(Action, f64)> {
-        let mut argmax = None;
-        for action in self.action_map.actions.keys() {
-            /* if seen_edges[action.1] { continue } */
-            let mu = self.mu(action)
-                .unwrap();
-            match argmax {
-                Some((_, max_mu)) => {
-                    if max_mu < mu {
-                        argmax = Some((*action, mu))
-                    }
-                },
-                None => argmax = Some((*action, mu))
-            }
-        }
-        argmax
+        best = best.min(total);
+        next = total;
     }
+    returns
 }
 
-#[derive(Default)]
-pub struct GraphMap<T: Neighborhood, const C: usize, const N: usize, const E: usize> {
-    graphs: HashMap<ColoredGraph<T, C, N>, GraphData>
+fn to_key(n: usize, relabeling: &Relabeling, (color, pos): Action) -> Action {
+    if relabeling.is_identity() { return (color, pos) }
+    (relabeling.color(color), edge_to_pos(n, relabeling.edge(pos_to_edge(n, pos))))
+}
+
+fn from_key(n: usize, relabeling: &Relabeling, (color, pos): Action) -> Action {
+    if relabeling.is_identity() { return (color, pos) }
+    (relabeling.color_back(color), edge_to_pos(n, relabeling.edge_back(pos_to_edge(n, pos))))
 }
 
-impl<T: Neighborhood, const C: usize, const N: usize, const E: usize>
-GraphMap<T, C, N, E>
+/* the same on every run, unlike the hashes of a `HashMap` */
+fn fingerprint<T: Neighborhood, const C: usize>(graph: &ColoredGraph<T, C>) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    graph.hash(&mut hasher);
+    hasher.finish()
+}
+
+/* entries of a `GraphMap`, and roughly the bytes they take */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct MapSize {
+    pub graphs: usize,
+    pub actions: usize,
+    pub bytes: usize
+}
+
+impl fmt::Display for MapSize {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} graphs, {} actions, ~{:.1} MB", self.graphs, self.actions, self.bytes as f64 / 1e6)
+    }
+}
+
+/* visit statistics, split into independently locked shards by the hash of the graph */
+pub struct GraphMap<T: Neighborhood, const C: usize> {
+    /* the vertices of every graph */
+    n: usize,
+    shards: Vec<Mutex<HashMap<ColoredGraph<T, C>, GraphData>>>,
+    memory: MemoryConfig,
+    backup: Backup,
+    discount: f64,
+    /* graphs are keyed by their canonical forms, and actions relabeled to match */
+    symmetry: Symmetry,
+    /* counts episodes, to date visits for eviction */
+    clock: AtomicU64,
+    graphs: AtomicUsize,
+    actions: AtomicUsize,
+    /* one worker evicts while the others carry on */
+    evicting: Mutex<()>
+}
+
+impl<T: Neighborhood, const C: usize>
+GraphMap<T, C>
 {
+    pub fn new(n: usize, shards: usize, memory: &MemoryConfig, selection: &SelectionConfig, symmetry: Symmetry) -> Self {
+        GraphMap {
+            n,
+            shards: (0..shards).map(|_| Mutex::default()).collect(),
+            memory: memory.clone(),
+            backup: selection.backup,
+            discount: selection.discount,
+            symmetry,
+            clock: AtomicU64::new(0),
+            graphs: AtomicUsize::new(0),
+            actions: AtomicUsize::new(0),
+            evicting: Mutex::new(())
+        }
+    }
+
+    pub fn size(&self) -> MapSize {
+        let (graphs, actions) = (self.graphs.load(Ordering::Relaxed), self.actions.load(Ordering::Relaxed));
+        /* a hash map spends a control byte per bucket and keeps at most 7/8 of its buckets full */
+        let bytes = |entries: usize, entry: usize| entries * (entry + 1) * 8 / 7;
+        MapSize {
+            graphs,
+            actions,
+            bytes: bytes(graphs, std::mem::size_of::<(ColoredGraph<T, C>, GraphData)>())
+                + graphs * self.n * std::mem::size_of::<[T; C]>()
+                + bytes(actions, std::mem::size_of::<(Action, ActionStats)>())
+        }
+    }
+
+    /* whether the size is worth reporting: it is only ever cut when capped */
+    pub fn capped(&self) -> bool {
+        self.memory.capped()
+    }
+
+    /* the fraction of the map within the caps, below 1 when over them */
+    fn headroom(&self, size: MapSize) -> f64 {
+        let graphs = self.memory.max_graphs.map(|max| max as f64 / size.graphs as f64);
+        let bytes = self.memory.max_megabytes.map(|max| max * 1e6 / size.bytes as f64);
+        graphs.into_iter().chain(bytes).fold(f64::INFINITY, f64::min)
+    }
+
+    /* smallest first out */
+    fn priority(&self, data: &GraphData) -> (Uzz, Uzz) {
+        match self.memory.eviction {
+            Eviction::Visits => (data.n_visits, data.last_visit),
+            Eviction::Lru => (data.last_visit, data.n_visits),
+            Eviction::Age => (data.first_visit, data.n_visits)
+        }
+    }
+
+    /* drops each shard to 7/8 of the caps by priority, never dropping `roots` */
+    pub fn evict(&self, roots: &HashSet<ColoredGraph<T, C>>) {
+        let Ok(_evicting) = self.evicting.try_lock()
+            else { return };
+        let mut headroom = self.headroom(self.size());
+        if headroom >= 1.0 { return }
+        /* low priority graphs tend to have few actions, so a byte cap may take a few rounds */
+        while headroom < 8.0 / 7.0 {
+            let keep = headroom * 7.0 / 8.0;
+            let (mut graphs, mut actions) = (0, 0);
+            for shard in &self.shards {
+                let mut shard = shard.lock().unwrap();
+                let evicted = shard.len() - (shard.len() as f64 * keep) as usize;
+                /* ties in priority go by a fixed hash of the graph, so that exactly `evicted` graphs go, the same ones on every run */
+                let mut candidates: Vec<((Uzz, Uzz), u64, &ColoredGraph<T, C>)> = shard.iter()
+                    .filter(|(graph, _)| !roots.contains(*graph))
+                    .map(|(graph, data)| (self.priority(data), fingerprint(graph), graph))
+                    .collect();
+                let evicted = evicted.min(candidates.len());
+                if evicted == 0 { continue }
+                candidates.select_nth_unstable_by_key(evicted - 1, |&(priority, fingerprint, _)| (priority, fingerprint));
+                let doomed: Vec<ColoredGraph<T, C>> = candidates[..evicted]
+                    .iter()
+                    .map(|&(_, _, graph)| graph.clone())
+                    .collect();
+                for graph in doomed {
+                    let data = shard.remove(&graph).expect("a graph of this shard");
+                    graphs += 1;
+                    actions += data.action_map.actions.len();
+                }
+            }
+            self.graphs.fetch_sub(graphs, Ordering::Relaxed);
+            self.actions.fetch_sub(actions, Ordering::Relaxed);
+            if graphs == 0 { break }
+            headroom = self.headroom(self.size());
+        }
+        println!("\rgraph map: evicted down to {}", self.size());
+    }
+
+    /* the statistics of `graph`, created on its first visit */
+    fn visit<R>(&self, graph: &ColoredGraph<T, C>, now: Uzz, f: impl FnOnce(&mut GraphData) -> R) -> R {
+        let mut shard = self.shard(graph);
+        let data = shard
+            .entry(graph.clone())
+            .or_insert_with(|| {
+                self.graphs.fetch_add(1, Ordering::Relaxed);
+                GraphData { first_visit: now, ..Default::default() }
+            });
+        data.last_visit = now;
+        f(data)
+    }
+
+    fn key<'g>(&self, graph: &'g ColoredGraph<T, C>) -> (Cow<'g, ColoredGraph<T, C>>, Relabeling) {
+        if self.symmetry.is_trivial() { return (Cow::Borrowed(graph), Relabeling::identity()) }
+        let (key, relabeling) = self.symmetry.canonical(graph);
+        (Cow::Owned(key), relabeling)
+    }
+
+    fn shard(&self, graph: &ColoredGraph<T, C>) -> MutexGuard<'_, HashMap<ColoredGraph<T, C>, GraphData>> {
+        let shard = match self.shards.len() {
+            1 => 0,
+            shards => fingerprint(graph) as usize % shards
+        };
+        self.shards[shard].lock().unwrap()
+    }
+
+    pub fn visits(&self) -> Vec<Visit> {
+        self.shards
+            .iter()
+            .flat_map(|shard| shard
+                .lock()
+                .unwrap()
+                .iter()
+                .map(|(graph, data)| Visit {
+                    graph: graph.graph6s(),
+                    n_visits: data.n_visits,
+                    actions: data.action_map.actions
+                        .iter()
+                        .map(|(&(color, pos), stats)| VisitAction { color, pos, q_ga: stats.q_ga, n_ga: stats.n_ga, value: Some(stats.value) })
+                        .collect(),
+                    first_visit: data.first_visit,
+                    last_visit: data.last_visit
+                })
+                .collect::<Vec<_>>())
+            .collect()
+    }
+
+    /* the clock resumes after the latest restored visit, so that eviction still sees the restored graphs as older */
+    pub fn restore(&mut self, visits: &[Visit]) -> Result<(), Graph6Error> {
+        let clock = self.clock.get_mut();
+        *clock = visits.iter().map(|visit| visit.last_visit).fold(*clock, Uzz::max);
+        for visit in visits {
+            let graph = ColoredGraph::from_graph6s(&visit.graph, self.n)?;
+            let actions = visit.actions
+                .iter()
+                .map(|&VisitAction { color, pos, q_ga, n_ga, value }|
+                    ((color, pos), ActionStats { q_ga, n_ga, value: value.unwrap_or(q_ga as f64) }))
+                .collect();
+            let data = GraphData {
+                n_visits: visit.n_visits,
+                first_visit: visit.first_visit,
+                last_visit: visit.last_visit,
+                action_map: ActionMap { actions }
+            };
+            self.graphs.fetch_add(1, Ordering::Relaxed);
+            self.actions.fetch_add(data.action_map.actions.len(), Ordering::Relaxed);
+            self.shard(&graph).insert(graph, data);
+        }
+        Ok(())
+    }
+
+    /* `policy` chooses among the actions taken from this graph before and the steepest untaken one, if any */
     pub fn next_action(
         &self,
-        actions: &mut ActionMatrix<T, C, N, E>
+        actions: &mut ActionMatrix<T, C>,
+        policy: &dyn SelectionPolicy,
+        rng: &mut dyn RngCore
     ) -> Option<Action>
     {
         let default_graph_data = GraphData::default();
-        let graph_data = self
-            .graphs
-            .get(&actions.graph);
+        let (key, relabeling) = self.key(&actions.graph);
+        let shard = self.shard(&key);
+        let graph_data = shard
+            .get(&key);
         
         let graph_data = graph_data.unwrap_or(&default_graph_data);
-        let best_visited = graph_data.visited_argmax();
-        let default_nu = graph_data.default_nu();
+        let n_g = graph_data.n_visits;
+        let mut candidates: Vec<Candidate> = graph_data.visited()
+            .into_iter()
+            .map(|candidate| Candidate { action: from_key(self.n, &relabeling, candidate.action), ..candidate })
+            .collect();
 
         // todo!("would be nice to implement this with a general predicate in the priority_queue crate")
         let action_queue = actions.actions_mut();
         let mut popped_actions = vec![];
 
-        let (best_action, _) = loop {
-            let best_unvisited: Option<(Action, Iyy)> = loop {
-                let Some((action, q_ga)) = action_queue.peek()
-                    else { break None };
-                if graph_data.action_map.actions.contains_key(action) {
-                    popped_actions.push(action_queue.pop().unwrap());
-                }
-                else {
-                    break Some((*action, *q_ga))
-                }
-            };
-
-            while let Some((action, q_ga)) = popped_actions.pop() {
-                action_queue.push(action, q_ga);
+        let best_unvisited: Option<(Action, Iyy)> = loop {
+            let Some((action, q_ga)) = action_queue.peek()
+                else { break None };
+            if graph_data.action_map.actions.contains_key(&to_key(self.n, &relabeling, *action)) {
+                popped_actions.push(action_queue.pop().unwrap());
+            }
+            else {
+                break Some((*action, *q_ga))
             }
-
-            match (best_visited, best_unvisited) {
-                (None, None) => {
-                    panic!("Couldn't find an action!")
-                }
-                (None, Some((action, q_ga))) => break (action, Some(q_ga)),
-                (Some((action, _)), None) => break (action, None),
-                (Some((v_action, mu_ga)), 
-                Some((u_action, q_ga))) => {
-                    if mu_ga >= q_ga as f64 + default_nu {
-                        break (v_action, None)
-                    }
-                    else {
-                        break (u_action, Some(q_ga))
-                    }
-                }
-            };
         };
+
+        while let Some((action, q_ga)) = popped_actions.pop() {
+            action_queue.push(action, q_ga);
+        }
+        drop(shard);
+
+        /* visited actions come first, and so win ties */
+        candidates.extend(best_unvisited.map(|(action, q_ga)| Candidate { action, slope: q_ga, value: q_ga as f64, n_ga: 0 }));
+        if candidates.is_empty() { return None }
+        let best_action = select(policy, n_g, &candidates, rng);
         actions.act(best_action);
         Some(best_action)
 
     }
 
     pub fn update_counts(
-        &mut self,
-        score_keeper: &mut ScoreKeeper<T, C, N, E>,
-        chosen_root: &mut ActionMatrix<T, C, N, E>,
+        &self,
+        score_keeper: &SharedKeeper<T, C>,
+        chosen_root: &mut ActionMatrix<T, C>,
         actions_taken: Vec<Action>
-    ) -> Result<(), ScoreUpdate>
+    )
     
     {
-        let graph_data = self.graphs.entry(chosen_root.graph().clone())
-            .or_insert(GraphData::default());
-        graph_data.n_visits += 1;
-
-        for best_action in actions_taken {
-            let graph_data = self.graphs.entry(chosen_root.graph().clone())
-                .or_insert(GraphData::default());
-            let q_ga = chosen_root.slope(best_action);
-            graph_data.record(best_action, q_ga.copied());
+        if self.headroom(self.size()) < 1.0 {
+            self.evict(&score_keeper.root_graphs())
+        }
+        let now = self.clock.fetch_add(1, Ordering::Relaxed) + 1;
+        let (key, _) = self.key(chosen_root.graph());
+        self.visit(&key, now, |data| data.n_visits += 1);
+
+        /* slopes are known on the way, returns only at the end of the episode */
+        let mut steps = vec![];
+        for (depth, best_action) in (1..).zip(actions_taken) {
+            let q_ga = chosen_root.slope(best_action).copied();
+            let (key, relabeling) = self.key(chosen_root.graph());
+            let key_action = to_key(self.n, &relabeling, best_action);
+            if self.backup == Backup::Slope {
+                self.record(&key, now, key_action, q_ga, q_ga.unwrap_or_default() as f64)
+            }
+            else {
+                steps.push((key.into_owned(), key_action, q_ga, chosen_root.total()))
+            }
             chosen_root.act(best_action);
-            if let ScoreUpdate::Done = score_keeper.update(chosen_root) {
-                return Err(ScoreUpdate::Done)
+            match score_keeper.update(chosen_root) {
+                ScoreUpdate::Done => break,
+                ScoreUpdate::Better => score_keeper.improved_at.store(depth, Ordering::Relaxed),
+                _ => {}
             }
         }
-        Ok(())
+        let totals: Vec<Iyy> = steps.iter().map(|&(_, _, _, total)| total).collect();
+        let returns = returns(&totals, chosen_root.total(), self.backup, self.discount);
+        for ((key, key_action, q_ga, _), value) in steps.into_iter().zip(returns) {
+            self.record(&key, now, key_action, q_ga, value)
+        }
+    }
+
+    fn record(&self, key: &ColoredGraph<T, C>, now: Uzz, action: Action, q_ga: Option<Iyy>, value: f64) {
+        if self.visit(key, now, |data| data.record(action, q_ga, value, self.backup)) {
+            self.actions.fetch_add(1, Ordering::Relaxed);
+        }
     }
     
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod search_map_tests {
+    use rand::seq::SliceRandom;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use crate::colored_graph::neighborhood::U32N;
+    use crate::learning_loop::{play_episode, selection::Selection};
+    use crate::strategy::{Quiet, SearchError};
+    use super::*;
+
+    #[test]
+    fn eviction_respects_caps_and_roots() {
+        const E: usize = 136;
+        let mut rng = StdRng::seed_from_u64(1);
+        for eviction in [Eviction::Visits, Eviction::Lru, Eviction::Age] {
+            let memory = MemoryConfig { max_graphs: Some(100), eviction, ..Default::default() };
+            let g_map = GraphMap::<U32N, 2>::new(17, 4, &memory, &Default::default(), Symmetry::default());
+            let start = ActionMatrix::new(ColoredGraph::uniformly_random(17, &mut rng), [4, 4]);
+            /* pinned roots may outnumber the cap otherwise */
+            let mut keeper = ScoreKeeper::new(start, 10, Symmetry::default());
+            let mut quiet = Quiet;
+            let shared = SharedKeeper::new(&mut keeper, &mut quiet);
+            for _ in 0..200 {
+                play_episode(&g_map, &shared, E/4, &Selection::new(4.5, &Default::default()), &mut rng).unwrap();
+                assert!(g_map.size().graphs <= 100 + E/4 + 1);
+            }
+
+            let graphs: usize = g_map.shards.iter().map(|shard| shard.lock().unwrap().len()).sum();
+            let actions: usize = g_map.shards
+                .iter()
+                .map(|shard| shard.lock().unwrap().values().map(|data| data.action_map.actions.len()).sum::<usize>())
+                .sum();
+            assert_eq!((g_map.size().graphs, g_map.size().actions), (graphs, actions));
+            /* a root only has statistics once an episode has moved on from it */
+            let roots: Vec<_> = shared.root_graphs()
+                .into_iter()
+                .filter(|root| g_map.shard(root).contains_key(root))
+                .collect();
+            g_map.evict(&shared.root_graphs());
+            assert!(g_map.size().graphs <= 100);
+            for root in roots {
+                assert!(g_map.shard(&root).contains_key(&root))
+            }
+        }
+    }
+
+    #[test]
+    fn eviction_breaks_ties() {
+        let mut rng = StdRng::seed_from_u64(4);
+        let graphs: Vec<ColoredGraph<U32N, 2>> = (0..100).map(|_| ColoredGraph::uniformly_random(17, &mut rng)).collect();
+        let roots = HashSet::from([graphs[0].clone()]);
+        let survivors = |order: &[ColoredGraph<U32N, 2>]| {
+            let memory = MemoryConfig { max_graphs: Some(70), ..Default::default() };
+            let g_map = GraphMap::<U32N, 2>::new(17, 1, &memory, &Default::default(), Symmetry::default());
+            /* visited together, so every priority is the same */
+            for graph in order {
+                g_map.visit(graph, 1, |data| data.n_visits += 1)
+            }
+            g_map.evict(&roots);
+            let shard = g_map.shard(&graphs[0]);
+            assert_eq!((shard.len(), g_map.size().graphs), (61, 61));
+            assert!(shard.contains_key(&graphs[0]));
+            shard.keys().cloned().collect::<HashSet<_>>()
+        };
+        let reversed: Vec<_> = graphs.iter().rev().cloned().collect();
+        assert!(survivors(&graphs) == survivors(&reversed));
+    }
+
+    #[test]
+    fn restored_graphs_keep_their_visit_clocks() {
+        let mut rng = StdRng::seed_from_u64(5);
+        let graphs: Vec<ColoredGraph<U32N, 2>> = (0..3).map(|_| ColoredGraph::uniformly_random(17, &mut rng)).collect();
+        let memory = MemoryConfig::default();
+        let g_map = GraphMap::<U32N, 2>::new(17, 1, &memory, &Default::default(), Symmetry::default());
+        for (now, graph) in (1..).zip(graphs.iter().chain(&graphs[..1])) {
+            g_map.clock.store(now, Ordering::Relaxed);
+            g_map.visit(graph, now, |data| data.n_visits += 1)
+        }
+
+        let mut restored = GraphMap::<U32N, 2>::new(17, 1, &memory, &Default::default(), Symmetry::default());
+        restored.restore(&g_map.visits()).unwrap();
+        let dates = |g_map: &GraphMap<U32N, 2>, graph| {
+            let shard = g_map.shard(graph);
+            let data = shard.get(graph).unwrap();
+            (data.first_visit, data.last_visit)
+        };
+        assert_eq!(dates(&restored, &graphs[0]), (1, 4));
+        assert_eq!(dates(&restored, &graphs[2]), (3, 3));
+        assert_eq!(restored.clock.load(Ordering::Relaxed), 4);
+    }
+
+    #[test]
+    fn episodes_without_actions_fail() {
+        let mut rng = StdRng::seed_from_u64(2);
+        let mut start = ActionMatrix::<U32N, 2>::new(ColoredGraph::uniformly_random(17, &mut rng), [4, 4]);
+        start.actions_mut().clear();
+        let g_map = GraphMap::new(17, 1, &MemoryConfig::default(), &Default::default(), Symmetry::default());
+        let mut keeper = ScoreKeeper::new(start, 10, Symmetry::default());
+        let mut quiet = Quiet;
+        let shared = SharedKeeper::new(&mut keeper, &mut quiet);
+        let selection = Selection::new(4.5, &Default::default());
+        assert_eq!(play_episode(&g_map, &shared, 1, &selection, &mut rng), Err(SearchError::NoAction));
+        assert_eq!(play_episode(&g_map, &shared, 0, &selection, &mut rng), Ok(()));
+    }
+
+    /* due every `every`, counting how often it was asked */
+    struct Periodic {
+        every: Option<Duration>,
+        asked: usize,
+        saved: Vec<SearchState>
+    }
+
+    impl Observer<U32N, 2> for Periodic {
+        fn checkpoint_due(&mut self, finished: bool) -> Option<bool> {
+            self.asked += 1;
+            finished.then_some(false)
+        }
+        fn next_checkpoint(&self) -> Option<Duration> { self.every }
+        fn checkpoint(&mut self, state: SearchState) { self.saved.push(state) }
+    }
+
+    #[test]
+    fn checkpoints_skip_the_lock_until_due() {
+        let mut rng = StdRng::seed_from_u64(3);
+        let g_map = GraphMap::new(17, 1, &MemoryConfig::default(), &Default::default(), Symmetry::default());
+        for (every, asked) in [(Some(Duration::from_secs(3600)), 1), (Some(Duration::ZERO), 100), (None, 1)] {
+            let start = ActionMatrix::<U32N, 2>::new(ColoredGraph::uniformly_random(17, &mut rng), [4, 4]);
+            let mut keeper = ScoreKeeper::new(start, 10, Symmetry::default());
+            let mut observer = Periodic { every, asked: 0, saved: vec![] };
+            let shared = SharedKeeper::new(&mut keeper, &mut observer);
+            for episode in 0..100 {
+                shared.checkpoint(&g_map, (1, episode), false)
+            }
+            /* the last checkpoint is never skipped */
+            shared.checkpoint(&g_map, (1, 100), true);
+            assert_eq!(observer.asked, asked + 1);
+            assert_eq!(observer.saved.iter().map(|state| state.episode).collect::<Vec<_>>(), [100]);
+        }
+    }
+
+    #[test]
+    fn returns_and_backups() {
+        let totals = [10, 8, 9, 5];
+        assert_eq!(returns(&totals, 6, Backup::Mean, 0.5), [5.0, 3.0, 4.0, -1.0]);
+        assert_eq!(returns(&totals, 6, Backup::Discounted, 0.5), [2.375, 0.75, 3.5, -1.0]);
+        assert_eq!(returns(&[], 6, Backup::Max, 0.5), Vec::<f64>::new());
+
+        let action = (1, 0);
+        for (backup, value) in [(Backup::Slope, 2.0), (Backup::Mean, 3.0), (Backup::Max, 5.0)] {
+            let mut data = GraphData::default();
+            assert!(data.record(action, Some(2), 2.0, backup));
+            assert!(!data.record(action, Some(2), 5.0, backup));
+            assert!(!data.record(action, Some(2), 2.0, backup));
+            assert_eq!(data.visited(), [Candidate { action, slope: 2, value, n_ga: 3 }]);
+        }
+    }
+
+    #[test]
+    fn relabeled_colorings_share_statistics() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for permute_colors in [false, true] {
+            let symmetry = Symmetry::new(true, &[4, 4], permute_colors);
+            let graph = ColoredGraph::<U32N, 2>::uniformly_random(17, &mut rng);
+            let mut labels: Vec<usize> = (0..17).collect();
+            labels.shuffle(&mut rng);
+            let colors = if permute_colors { vec![1, 0] } else { vec![0, 1] };
+            let shuffle = Relabeling::new(labels).with_colors(colors);
+            let mut root = ActionMatrix::new(graph.clone(), [4, 4]);
+            let copy = ActionMatrix::new(graph.relabel(&shuffle), [4, 4]);
+
+            let mut quiet = Quiet;
+            let mut keeper = ScoreKeeper::new(root.clone(), 250, symmetry.clone());
+            assert!(matches!(keeper.update(&copy, &mut quiet), ScoreUpdate::Known));
+            let mut plain = ScoreKeeper::new(root.clone(), 250, Symmetry::default());
+            assert!(matches!(plain.update(&copy, &mut quiet), ScoreUpdate::Tie));
+
+            let g_map = GraphMap::new(17, 1, &MemoryConfig::default(), &Default::default(), symmetry);
+            let shared = SharedKeeper::new(&mut keeper, &mut quiet);
+            let action = (1 - graph.color((2, 5)).unwrap(), edge_to_pos(17, (2, 5)));
+            g_map.update_counts(&shared, &mut root, vec![action]);
+            let (key, relabeling) = g_map.key(copy.graph());
+            let moved = (shuffle.color(action.0), edge_to_pos(17, shuffle.edge((2, 5))));
+            let shard = g_map.shard(&key);
+            let data = shard.get(&key).unwrap();
+            assert_eq!(data.n_visits, 2);
+            assert!(data.action_map.actions.contains_key(&to_key(17, &relabeling, moved)));
+        }
+    }
+}
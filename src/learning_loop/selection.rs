@@ -0,0 +1,171 @@
+use rand::{Rng, RngCore};
+
+use crate::prelude::{Iyy, Uzz};
+use crate::action_matrix::Action;
+use crate::config::{Policy, SelectionConfig};
+
+/* an action open to an episode, with its slope and its value, which is the slope unless it was taken before */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Candidate {
+    pub action: Action,
+    pub slope: Iyy,
+    pub value: f64,
+    pub n_ga: Uzz
+}
+
+/* how an episode picks the next action at a graph among the actions it has taken there and the
+   steepest one it has not */
+pub trait SelectionPolicy: Sync {
+    /* an action of value `q` taken `n_ga` times in the `n_g` visits to its graph, where `prior` is its
+       share of the candidates */
+    fn score(&self, q: f64, n_g: Uzz, n_ga: Uzz, prior: f64) -> f64;
+
+    /* the temperature of the softmax over the candidates' slopes giving the priors, which are uniform otherwise */
+    fn prior_temperature(&self) -> Option<f64> { None }
+
+    /* the index of the candidate to take, the first of the best scores by default */
+    fn choose(&self, scores: &[f64], _rng: &mut dyn RngCore) -> usize {
+        argmax(scores)
+    }
+}
+
+fn argmax(scores: &[f64]) -> usize {
+    let mut best = 0;
+    for (i, &score) in scores.iter().enumerate() {
+        if score > scores[best] { best = i }
+    }
+    best
+}
+
+/* the built-in policies, with EXPLORE scaled by `explore_decay` every epoch */
+#[derive(Clone, Debug)]
+pub struct Selection {
+    policy: Policy,
+    explore: f64,
+    explore_decay: f64,
+    softmax_temperature: f64,
+    prior_temperature: f64
+}
+
+impl Selection {
+    pub fn new(explore: f64, config: &SelectionConfig) -> Self {
+        Selection {
+            policy: config.policy,
+            explore,
+            explore_decay: config.explore_decay,
+            softmax_temperature: config.softmax_temperature,
+            prior_temperature: config.prior_temperature
+        }
+    }
+
+    pub fn at_epoch(&self, epoch: usize) -> Self {
+        let explore = self.explore * self.explore_decay.powi(epoch as i32 - 1);
+        Selection { explore, ..self.clone() }
+    }
+}
+
+impl SelectionPolicy for Selection {
+    fn score(&self, q: f64, n_g: Uzz, n_ga: Uzz, prior: f64) -> f64 {
+        match self.policy {
+            Policy::Uct => q + self.explore * (n_g as f64).sqrt() / (1 + n_ga) as f64,
+            /* untried actions come first */
+            Policy::Ucb1 if n_ga == 0 => f64::INFINITY,
+            Policy::Ucb1 => q + self.explore * ((n_g as f64).ln() / n_ga as f64).sqrt(),
+            Policy::Puct => q + self.explore * prior * (n_g as f64).sqrt() / (1 + n_ga) as f64
+        }
+    }
+
+    fn prior_temperature(&self) -> Option<f64> {
+        (self.policy == Policy::Puct).then_some(self.prior_temperature)
+    }
+
+    /* samples with probabilities proportional to exp(score / temperature) for a positive temperature */
+    fn choose(&self, scores: &[f64], rng: &mut dyn RngCore) -> usize {
+        let best = argmax(scores);
+        if self.softmax_temperature == 0.0 || scores[best].is_infinite() { return best }
+        let weights: Vec<f64> = scores.iter()
+            .map(|score| ((score - scores[best]) / self.softmax_temperature).exp())
+            .collect();
+        let mut draw = rng.gen::<f64>() * weights.iter().sum::<f64>();
+        for (i, weight) in weights.iter().enumerate() {
+            if draw < *weight { return i }
+            draw -= weight
+        }
+        best
+    }
+}
+
+/* the choice of `policy` among `candidates` at a graph visited `n_g` times */
+pub fn select(policy: &dyn SelectionPolicy, n_g: Uzz, candidates: &[Candidate], rng: &mut dyn RngCore) -> Action {
+    let priors = match policy.prior_temperature() {
+        Some(temperature) => priors(candidates.iter().map(|candidate| candidate.slope), temperature),
+        None => vec![1.0 / candidates.len() as f64; candidates.len()]
+    };
+    let scores: Vec<f64> = candidates.iter()
+        .zip(priors)
+        .map(|(candidate, prior)| policy.score(candidate.value, n_g, candidate.n_ga, prior))
+        .collect();
+    candidates[policy.choose(&scores, rng)].action
+}
+
+/* the softmax of `slopes` at `temperature` */
+pub fn priors(slopes: impl Iterator<Item = Iyy> + Clone, temperature: f64) -> Vec<f64> {
+    let Some(max) = slopes.clone().max() else { return vec![] };
+    let weights: Vec<f64> = slopes
+        .map(|q_ga| ((q_ga - max) as f64 / temperature).exp())
+        .collect();
+    let total: f64 = weights.iter().sum();
+    weights.iter().map(|weight| weight / total).collect()
+}
+
+#[cfg(test)]
+mod selection_tests {
+    use rand::{SeedableRng, rngs::StdRng};
+
+    use super::*;
+
+    fn selection(policy: Policy, softmax_temperature: f64) -> Selection {
+        Selection::new(2.0, &SelectionConfig { policy, softmax_temperature, ..Default::default() })
+    }
+
+    #[test]
+    fn formulas() {
+        assert_eq!(selection(Policy::Uct, 0.0).score(3.0, 16, 1, 1.0), 3.0 + 2.0 * 4.0 / 2.0);
+        assert_eq!(selection(Policy::Ucb1, 0.0).score(3.0, 16, 0, 1.0), f64::INFINITY);
+        assert!((selection(Policy::Ucb1, 0.0).score(3.0, 16, 4, 1.0) - (3.0 + 2.0 * (16f64.ln() / 4.0).sqrt())).abs() < 1e-12);
+        assert_eq!(selection(Policy::Puct, 0.0).score(3.0, 16, 1, 0.25), 3.0 + 2.0 * 0.25 * 4.0 / 2.0);
+        assert_eq!(selection(Policy::Uct, 0.0).prior_temperature(), None);
+        assert_eq!(selection(Policy::Puct, 0.0).prior_temperature(), Some(1.0));
+    }
+
+    #[test]
+    fn exploration_decays() {
+        let config = SelectionConfig { explore_decay: 0.5, ..Default::default() };
+        let selection = Selection::new(4.0, &config);
+        assert_eq!(selection.at_epoch(1).score(0.0, 1, 0, 1.0), 4.0);
+        assert_eq!(selection.at_epoch(3).score(0.0, 1, 0, 1.0), 1.0);
+    }
+
+    #[test]
+    fn priors_are_a_softmax() {
+        let priors = priors([2, 2, 1].into_iter(), 1.0);
+        assert!((priors.iter().sum::<f64>() - 1.0).abs() < 1e-12);
+        assert_eq!(priors[0], priors[1]);
+        assert!(priors[2] < priors[0]);
+        assert_eq!(super::priors([].into_iter(), 1.0), Vec::<f64>::new());
+    }
+
+    #[test]
+    fn softmax_samples() {
+        let mut rng = StdRng::seed_from_u64(0);
+        let scores = [0.0, 1.0, 1.0];
+        assert_eq!(selection(Policy::Uct, 0.0).choose(&scores, &mut rng), 1);
+        let softmax = selection(Policy::Uct, 1.0);
+        let mut counts = [0; 3];
+        for _ in 0..3_000 { counts[softmax.choose(&scores, &mut rng)] += 1 }
+        /* e : e : 1 */
+        assert!(counts.iter().all(|&count| count > 300));
+        assert!(counts[0] < counts[1] && counts[0] < counts[2]);
+        assert_eq!(softmax.choose(&[0.0, f64::INFINITY], &mut rng), 1);
+    }
+}
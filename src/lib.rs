@@ -1,4 +1,8 @@
 pub mod prelude;
+pub mod config;
+pub mod dispatch;
 pub mod colored_graph;
 pub mod action_matrix;
+pub mod checkpoint;
+pub mod strategy;
 pub mod learning_loop;
@@ -1,49 +1,26 @@
-use std::io;
-use mcts_ramsey::{learning_loop::*, prelude::*, colored_graph::neighborhood::*};
-
-const C: usize = S.len();
-const E: usize = choose_two(N);
-
-fn main() {
-
-    let args: Vec<String> = std::env::args().collect();
-    if args.contains(&String::from("clean")) {
-        /* https://stackoverflow.com/a/69987121 */
-        for path in std::fs::read_dir("./plots/").unwrap() {
-            let path = path.unwrap().path();
-            let extension = path.extension().unwrap();
-            use std::ffi::OsStr;
-            if extension == OsStr::new("svg") {
-                std::fs::remove_file(path).unwrap();
-            }
+mod cli;
+mod commands;
+
+use std::process::ExitCode;
+
+use clap::Parser;
+use cli::{Cli, Command};
+
+fn main() -> ExitCode {
+    let cli = Cli::parse();
+    let result = match cli.command.unwrap_or(Command::Search(Box::new(cli.search))) {
+        Command::Search(args) => commands::search(*args),
+        Command::Verify(args) => commands::verify(args),
+        Command::Render(args) => commands::render(args),
+        Command::Convert(args) => commands::convert(args),
+        Command::Clean(args) => commands::clean(args)
+    };
+
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(failure) => {
+            eprintln!("{failure}");
+            failure.exit_code()
         }
-        println!("plots/*.svg cleared 😊")
     }
-    else {
-        println!("To clear plots/*.svg, pass 'clean' as an argument 😊")
-    }
-
-    println!("Goal: prove R{S:?} > {N}.");
-    println!("EPOCHS   = {EPOCHS}");
-    println!("EPISODES = {EPISODES}");
-    println!("ROOTS    = {ROOTS}");
-    println!("EXPLORE  = {EXPLORE}");
-    println!("GUESS_P  = {GUESS_P:?}");
-    println!("Enter 'q' now to quit.");
-
-    let mut input = String::new();
-    io::stdin()
-        .read_line(&mut input)
-        .expect("Failed to read line");
-    if input.contains('q') { return }
-
-    use std::time::Instant;
-    let now = Instant::now();
-    
-    type T = UxxN<N>;
-
-    search::<T, C, N, E>();
-
-    let elapsed = now.elapsed();
-    println!("Elapsed: {elapsed:.3?}");
 }
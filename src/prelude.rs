@@ -1,35 +1,55 @@
-include!(concat!(env!("OUT_DIR"), "/constants.rs"));
+pub type Iyy = i64;
+pub type Uzz = u64;
 
 pub type Color = usize;
 pub type Vertex = usize;
 pub type Edge = (Vertex, Vertex);
 
+const fn gcd(mut a: Iyy, mut b: Iyy) -> Iyy {
+    while b != 0 {
+        (a, b) = (b, a % b)
+    }
+    a
+}
+
+/* exact as long as the result fits in an Iyy, and panics otherwise; `config` estimates larger ones in floating point */
 pub const fn choose(n: usize, k: usize) -> Iyy {
-    match (n, k) {
-        (_, 0) => 1,
-        (0, _) => 0,
-        _ => choose(n-1, k) + choose(n-1, k-1)
+    if k > n { return 0 }
+    let k = if k < n - k { k } else { n - k };
+    let mut binomial: Iyy = 1;
+    let mut i = 0;
+    while i < k {
+        /* binomial == choose(n, i) times n - i is a multiple of i + 1, so dividing first leaves no product
+           beyond choose(n, i+1) */
+        let divisor = gcd(binomial, (i + 1) as Iyy);
+        binomial = match (binomial / divisor).checked_mul((n - i) as Iyy / ((i + 1) as Iyy / divisor)) {
+            Some(binomial) => binomial,
+            None => panic!("choose(n, k) overflows an Iyy")
+        };
+        i += 1
     }
+    binomial
 }
 
 pub const fn choose_two(n: usize) -> usize {
     n*(n+1)/2-n
 }
 
-pub fn pos_to_edge<const N: usize>(pos: usize) -> Edge {
+/* the edges of K_n in lexicographic order */
+pub fn pos_to_edge(n: usize, pos: usize) -> Edge {
     let mut u = 0;
-    let mut pos_u_n_minus_1 = N - 2;
+    let mut pos_u_n_minus_1 = n - 2;
     while pos_u_n_minus_1 < pos {
-        pos_u_n_minus_1 += N - 2 - u;
+        pos_u_n_minus_1 += n - 2 - u;
         u += 1
     }
-    let v = N - 1 - (pos_u_n_minus_1 - pos);
+    let v = n - 1 - (pos_u_n_minus_1 - pos);
     (u, v)
 }
 
-pub fn edge_to_pos<const N: usize>((u, v): Edge) -> usize {
-    if u < v { u*(2*N-1-u)/2 + (v-u-1) }
-    else     { v*(2*N-1-v)/2 + (u-v-1) }
+pub fn edge_to_pos(n: usize, (u, v): Edge) -> usize {
+    if u < v { u*(2*n-1-u)/2 + (v-u-1) }
+    else     { v*(2*n-1-v)/2 + (u-v-1) }
 }
 
 #[cfg(test)]
@@ -38,6 +58,24 @@ mod math_tests {
     use itertools::Itertools;
     use super::*;
 
+    #[test]
+    fn pascals_rule() {
+        for n in 1..67 {
+            assert_eq!(choose(n, 0), 1);
+            assert_eq!(choose(n, n + 1), 0);
+            for k in 1..=n {
+                assert_eq!(choose(n, k), choose(n-1, k) + choose(n-1, k-1));
+            }
+        }
+    }
+
+    #[test]
+    fn choose_near_iyy_max() {
+        assert_eq!(choose(62, 31), 465428353255261088);
+        assert_eq!(choose(66, 33), 7219428434016265740);
+        assert!(std::panic::catch_unwind(|| choose(67, 33)).is_err());
+    }
+
     #[test]
     fn choose_two_consistent() {
         for n in 0..100 {
@@ -50,15 +88,15 @@ mod math_tests {
     #[test]
     fn pos_to_edge_test() {
         for (i, (u, v)) in (0..N).tuple_combinations().enumerate() {
-            assert_eq!(pos_to_edge::<N>(i), (u, v));
+            assert_eq!(pos_to_edge(N, i), (u, v));
         }
     }
 
     #[test]
     fn edge_to_pos_test() {
         for (i, (u, v)) in (0..N).tuple_combinations().enumerate() {
-            assert_eq!(edge_to_pos::<N>((u,v)), i);
-            assert_eq!(edge_to_pos::<N>((v,u)), i);
+            assert_eq!(edge_to_pos(N, (u,v)), i);
+            assert_eq!(edge_to_pos(N, (v,u)), i);
         }
     }
 }
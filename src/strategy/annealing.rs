@@ -0,0 +1,152 @@
+use rand::{Rng, RngCore};
+
+use crate::prelude::*;
+use crate::{action_matrix::*, colored_graph::neighborhood::Neighborhood};
+use crate::config::{AnnealingConfig, Proposal, Schedule};
+use super::{Budget, Observer, SearchError, SearchOutcome, SearchStats, SearchStrategy, checkpoint_best};
+
+/* Metropolis over single recolorings, reheating at the start of every epoch */
+pub struct Annealing {
+    temperature: f64,
+    final_temperature: f64,
+    schedule: Schedule,
+    patience: Uzz,
+    proposal: Proposal,
+    tournament: usize
+}
+
+impl Annealing {
+    pub fn new(config: &AnnealingConfig) -> Self {
+        Annealing {
+            temperature: config.temperature,
+            final_temperature: config.final_temperature,
+            schedule: config.schedule,
+            patience: config.patience,
+            proposal: config.proposal,
+            tournament: config.tournament
+        }
+    }
+
+    /* the temperature `step` steps into a cooling cycle of `length` steps */
+    fn temperature(&self, step: Uzz, length: Uzz) -> f64 {
+        let progress = step as f64 / length as f64;
+        match self.schedule {
+            Schedule::Linear =>
+                self.temperature + (self.final_temperature - self.temperature) * progress,
+            Schedule::Geometric | Schedule::Adaptive =>
+                self.temperature * (self.final_temperature / self.temperature).powf(progress)
+        }
+    }
+
+    /* a random recoloring and its slope, the steepest of `tournament` draws for slope proposals */
+    fn propose<T: Neighborhood, const C: usize, R: Rng + ?Sized>(
+        &self,
+        actions: &ActionMatrix<T, C>,
+        rng: &mut R
+    ) -> (Action, Iyy) {
+        let draws = match self.proposal {
+            Proposal::Uniform => 1,
+            Proposal::Slope => self.tournament
+        };
+        (0..draws)
+            .map(|_| {
+                let action = actions.graph().random_recoloring(rng).action(actions.graph().n());
+                (action, *actions.slope(action).unwrap())
+            })
+            .max_by_key(|&(_, q_ga)| q_ga)
+            .unwrap()
+    }
+}
+
+impl<T: Neighborhood, const C: usize>
+SearchStrategy<T, C> for Annealing {
+    fn search(
+        &mut self,
+        start: ActionMatrix<T, C>,
+        budget: &Budget,
+        observer: &mut dyn Observer<T, C>,
+        rng: &mut dyn RngCore
+    ) -> Result<SearchOutcome<ActionMatrix<T, C>>, SearchError> {
+        let mut best = start.clone();
+        let mut current = start;
+        let mut at = (budget.first_epoch, budget.first_episode);
+        let mut steps: Uzz = 0;
+        'search: for epoch in budget.epoch_range() {
+            observer.epoch(epoch);
+            let episodes = budget.episode_range(epoch);
+            let (mut step, mut stale): (Uzz, Uzz) = (episodes.start, 0);
+            for episode in episodes {
+                at = (epoch, episode);
+                checkpoint_best(observer, at, &best, false);
+                if best.total() == 0 || budget.expired() { break 'search }
+                let temperature = self.temperature(step, budget.episodes);
+                step += 1;
+                steps += 1;
+                /* the total drops by the slope, so uphill moves pass with probability exp(slope / temperature) */
+                let (action, q_ga) = self.propose(&current, rng);
+                if q_ga >= 0 || rng.gen::<f64>() < (q_ga as f64 / temperature).exp() {
+                    current.act(action)
+                }
+                if current.total() < best.total() {
+                    best = current.clone();
+                    observer.improved(&best);
+                    stale = 0
+                }
+                else {
+                    stale += 1
+                }
+                if self.schedule == Schedule::Adaptive && stale >= self.patience {
+                    (step, stale) = (0, 0)
+                }
+                at = (epoch, episode + 1);
+            }
+        }
+        checkpoint_best(observer, at, &best, true);
+        Ok(SearchOutcome::new(best, budget, SearchStats::new(budget, at.0, steps)))
+    }
+}
+
+#[cfg(test)]
+mod annealing_tests {
+    use rand::{SeedableRng, rngs::StdRng};
+    use crate::colored_graph::{ColoredGraph, neighborhood::U8N};
+    use crate::strategy::strategy_tests::check_finds_c5;
+    use super::*;
+
+    #[test]
+    fn annealing_finds_c5() {
+        for schedule in [Schedule::Geometric, Schedule::Linear, Schedule::Adaptive] {
+            for proposal in [Proposal::Uniform, Proposal::Slope] {
+                let config = AnnealingConfig { schedule, proposal, ..Default::default() };
+                check_finds_c5(&mut Annealing::new(&config))
+            }
+        }
+    }
+
+    #[test]
+    fn cooling_schedules() {
+        for schedule in [Schedule::Geometric, Schedule::Linear, Schedule::Adaptive] {
+            let config = AnnealingConfig { temperature: 4.0, final_temperature: 0.25, schedule, ..Default::default() };
+            let annealing = Annealing::new(&config);
+            assert_eq!(annealing.temperature(0, 100), 4.0);
+            assert!((annealing.temperature(100, 100) - 0.25).abs() < 1e-12);
+            assert!((1..=100).all(|step| annealing.temperature(step, 100) < annealing.temperature(step - 1, 100)));
+        }
+        let geometric = Annealing::new(&AnnealingConfig { temperature: 4.0, final_temperature: 0.25, ..Default::default() });
+        assert!((geometric.temperature(50, 100) - 1.0).abs() < 1e-12);
+    }
+
+    #[test]
+    fn slope_proposals_are_steeper() {
+        let mut rng = StdRng::seed_from_u64(1);
+        let actions = ActionMatrix::<U8N, 2>::new(ColoredGraph::uniformly_random(8, &mut rng), [3, 3]);
+        let steepest = actions.actions.peek().map(|(_, &q_ga)| q_ga).unwrap();
+        let uniform = Annealing::new(&AnnealingConfig::default());
+        let slope = Annealing::new(&AnnealingConfig { proposal: Proposal::Slope, tournament: 1_000, ..Default::default() });
+        for _ in 0..100 {
+            let (action, q_ga) = uniform.propose(&actions, &mut rng);
+            assert_eq!(actions.slope(action), Some(&q_ga));
+        }
+        assert_eq!(slope.propose(&actions, &mut rng).1, steepest);
+    }
+}
@@ -0,0 +1,484 @@
+pub mod annealing;
+pub mod tabu;
+
+use std::{fmt, ops::{Range, RangeInclusive}, path::PathBuf, time::{Duration, Instant}};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+use rand::{RngCore, SeedableRng, distributions::WeightedIndex, rngs::StdRng};
+
+use crate::{prelude::*, config::*, dispatch::*, learning_loop::Mcts};
+use crate::{action_matrix::ActionMatrix, colored_graph::{ColoredGraph, display::Graph6Error, neighborhood::Neighborhood}};
+use crate::checkpoint::{Checkpoint, Checkpointer, SearchState, Visit};
+use self::{annealing::Annealing, tabu::Tabu};
+
+/* `epochs` rounds of `episodes` units of work each, where each strategy decides what a unit is */
+#[derive(Debug)]
+pub struct Budget {
+    pub epochs: usize,
+    pub episodes: Uzz,
+    pub deadline: Option<Instant>,
+    /* where a resumed search picks up */
+    pub first_epoch: usize,
+    pub first_episode: Uzz,
+    pub started: Instant,
+    /* seconds spent before a resume */
+    pub earlier: f64,
+    /* whether the deadline cut work short, rather than passing after the last step */
+    timed_out: AtomicBool
+}
+
+impl Budget {
+    pub fn new(config: &SearchConfig) -> Self {
+        Budget::resume(config, 1, 0, 0.0)
+    }
+
+    /* the rest of the budget after `elapsed` seconds and `episode` episodes of `epoch` */
+    pub fn resume(config: &SearchConfig, epoch: usize, episode: Uzz, elapsed: f64) -> Self {
+        Budget {
+            epochs: config.epochs,
+            episodes: config.episodes,
+            deadline: config.time_limit
+                .map(|seconds| Instant::now() + Duration::from_secs_f64((seconds - elapsed).max(0.0))),
+            first_epoch: epoch,
+            first_episode: episode,
+            started: Instant::now(),
+            earlier: elapsed,
+            timed_out: AtomicBool::new(false)
+        }
+    }
+
+    pub fn epoch_range(&self) -> RangeInclusive<usize> {
+        self.first_epoch..=self.epochs
+    }
+
+    pub fn episode_range(&self, epoch: usize) -> Range<Uzz> {
+        let first = if epoch == self.first_epoch { self.first_episode } else { 0 };
+        first..self.episodes
+    }
+
+    /* whether to stop for the deadline; only ask with work left, since a yes counts as the deadline stopping the search */
+    pub fn expired(&self) -> bool {
+        let expired = self.deadline.is_some_and(|deadline| Instant::now() >= deadline);
+        if expired { self.timed_out.store(true, Ordering::Relaxed) }
+        expired
+    }
+
+    pub fn timed_out(&self) -> bool {
+        self.timed_out.load(Ordering::Relaxed)
+    }
+
+    /* in seconds, counting those before a resume */
+    pub fn elapsed(&self) -> f64 {
+        self.earlier + self.started.elapsed().as_secs_f64()
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StopReason {
+    Witness,
+    TimeLimit,
+    /* every epoch was played */
+    Exhausted
+}
+
+impl StopReason {
+    /* for a strategy that stopped with `count` monochromatic cliques left */
+    pub fn of(count: Iyy, budget: &Budget) -> Self {
+        if count == 0 { StopReason::Witness }
+        else if budget.timed_out() { StopReason::TimeLimit }
+        else { StopReason::Exhausted }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SearchStats {
+    /* the last epoch begun */
+    pub epochs: usize,
+    /* steps of this run, without those before a resume */
+    pub steps: Uzz,
+    /* in seconds, counting those before a resume */
+    pub elapsed: f64
+}
+
+impl SearchStats {
+    pub fn new(budget: &Budget, epochs: usize, steps: Uzz) -> Self {
+        SearchStats { epochs, steps, elapsed: budget.elapsed() }
+    }
+}
+
+/* the best coloring found, an `ActionMatrix` from a strategy or g6 strings from `search_config`,
+   and why and after how much work the search stopped */
+#[derive(Clone, Debug, PartialEq)]
+pub struct SearchOutcome<B = Vec<String>> {
+    pub best: B,
+    pub best_count: Iyy,
+    pub stop: StopReason,
+    pub stats: SearchStats,
+    /* the seed `search_config` drew the generator from, unknown to a strategy handed a generator */
+    pub seed: Option<u64>
+}
+
+impl<B> SearchOutcome<B> {
+    pub fn witness(&self) -> Option<&B> {
+        (self.stop == StopReason::Witness).then_some(&self.best)
+    }
+
+    pub fn map<D>(self, f: impl FnOnce(B) -> D) -> SearchOutcome<D> {
+        SearchOutcome { best: f(self.best), best_count: self.best_count, stop: self.stop, stats: self.stats, seed: self.seed }
+    }
+}
+
+impl<T: Neighborhood, const C: usize>
+SearchOutcome<ActionMatrix<T, C>> {
+    pub fn new(best: ActionMatrix<T, C>, budget: &Budget, stats: SearchStats) -> Self {
+        let best_count = best.total();
+        SearchOutcome { best, best_count, stop: StopReason::of(best_count, budget), stats, seed: None }
+    }
+}
+
+/* a search that could not run to the end of its budget */
+#[derive(Clone, Debug, PartialEq)]
+pub enum SearchError {
+    Config(ConfigError),
+    /* an episode found no recoloring to make */
+    NoAction
+}
+
+impl fmt::Display for SearchError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SearchError::Config(error) => write!(f, "{error}"),
+            SearchError::NoAction => write!(f, "an episode found no action to take")
+        }
+    }
+}
+
+impl std::error::Error for SearchError {}
+
+impl From<ConfigError> for SearchError {
+    fn from(error: ConfigError) -> Self {
+        SearchError::Config(error)
+    }
+}
+
+/* `Send` so that worker threads can report through it */
+pub trait Observer<T: Neighborhood, const C: usize>: Send {
+    fn epoch(&mut self, _epoch: usize) {}
+    /* a strictly better coloring than any seen before */
+    fn improved(&mut self, _best: &ActionMatrix<T, C>) {}
+    /* `Some(with_visits)` when the strategy should hand over a `SearchState`, always when `finished` */
+    fn checkpoint_due(&mut self, _finished: bool) -> Option<bool> { None }
+    /* how soon `checkpoint_due` may say yes again, so that threads can skip asking until then; `None` for never */
+    fn next_checkpoint(&self) -> Option<Duration> { None }
+    fn checkpoint(&mut self, _state: SearchState) {}
+}
+
+/* checkpoints a strategy that only needs its best coloring back */
+pub(crate) fn checkpoint_best<T: Neighborhood, const C: usize>(
+    observer: &mut dyn Observer<T, C>,
+    (epoch, episode): (usize, Uzz),
+    best: &ActionMatrix<T, C>,
+    finished: bool
+) {
+    if observer.checkpoint_due(finished).is_some() {
+        observer.checkpoint(SearchState::new(epoch, episode, std::slice::from_ref(best), vec![]))
+    }
+}
+
+pub trait SearchStrategy<T: Neighborhood, const C: usize> {
+    /* the best coloring found from `start` within `budget`, drawing every random choice from `rng` */
+    fn search(
+        &mut self,
+        start: ActionMatrix<T, C>,
+        budget: &Budget,
+        observer: &mut dyn Observer<T, C>,
+        rng: &mut dyn RngCore
+    ) -> Result<SearchOutcome<ActionMatrix<T, C>>, SearchError>;
+
+    /* state beyond the start coloring from a checkpoint, before `search` resumes */
+    fn restore(&mut self, _roots: Vec<ActionMatrix<T, C>>, _visits: &[Visit]) -> Result<(), Graph6Error> {
+        Ok(())
+    }
+}
+
+/* prints each improvement and renders it as an SVG, and writes the checkpoints */
+pub struct Reporter {
+    name: String,
+    output_dir: PathBuf,
+    description: String,
+    checkpoints: Option<Checkpointer>
+}
+
+impl Reporter {
+    /* `elapsed` seconds were spent before a resumed search */
+    pub fn new(config: &SearchConfig, elapsed: f64) -> Self {
+        Reporter {
+            name: format!("r{:?}_{}", config.s, config.n),
+            output_dir: config.output_dir.clone(),
+            description: config.to_toml(),
+            checkpoints: Checkpointer::new(config, elapsed)
+        }
+    }
+}
+
+impl<T: Neighborhood, const C: usize>
+Observer<T, C> for Reporter {
+    fn epoch(&mut self, epoch: usize) {
+        println!("==== EPOCH ==== {epoch}");
+    }
+
+    fn improved(&mut self, best: &ActionMatrix<T, C>) {
+        let count = best.total();
+        println!("score improved to {count} by");
+        if best.graph().n() <= 10 {
+            best.graph().show_neighborhoods();
+        }
+        if best.graph().n() <= 25 {
+            best.graph().show_matrix();
+            println!();
+        }
+        let graph6s = best.graph().graph6s();
+        println!("{graph6s:?}");
+        let rendered = best.graph()
+            .svg(self.name.clone())
+            .describe(&format!("{}score = {count}\ng6 = {graph6s:?}\n", self.description))
+            .render(&self.output_dir);
+        if let Err(error) = rendered {
+            eprintln!("could not write to {}: {error}", self.output_dir.display())
+        }
+        if count == 0 {
+            println!("==== DONE ====\nCheck out {}/{}*.svg 😊", self.output_dir.display(), self.name);
+        }
+    }
+
+    fn checkpoint_due(&mut self, finished: bool) -> Option<bool> {
+        self.checkpoints.as_ref()?.due(finished)
+    }
+
+    fn next_checkpoint(&self) -> Option<Duration> {
+        self.checkpoints.as_ref().map(Checkpointer::wait)
+    }
+
+    fn checkpoint(&mut self, state: SearchState) {
+        if let Some(checkpoints) = &mut self.checkpoints { checkpoints.save(state) }
+    }
+}
+
+/* ignores everything */
+pub struct Quiet;
+
+impl<T: Neighborhood, const C: usize>
+Observer<T, C> for Quiet {}
+
+/* a random coloring drawn from GUESS_P */
+pub fn start<T: Neighborhood, const C: usize>(
+    config: &SearchConfig,
+    rng: &mut dyn RngCore
+) -> ActionMatrix<T, C>
+{
+    let dist = WeightedIndex::new(config.guess_p())
+        .unwrap();
+    let graph = ColoredGraph::<T, C>::random(config.n, rng, &dist);
+    let s: [usize; C] = config.s
+        .clone()
+        .try_into()
+        .expect("S has C entries");
+    ActionMatrix::new(graph, s)
+}
+
+pub fn strategy<T: Neighborhood, const C: usize>(config: &SearchConfig)
+-> Box<dyn SearchStrategy<T, C>>
+{
+    match config.strategy {
+        Strategy::Mcts => Box::new(Mcts::new(config)),
+        Strategy::Tabu => Box::new(Tabu::new(&config.tabu)),
+        Strategy::Annealing => Box::new(Annealing::new(&config.annealing))
+    }
+}
+
+/*
+ * the generator of a search resumed at `episode` of `epoch`; checkpoints do not keep the generator state,
+ * so it is seeded from where the search resumes as well as from `seed`: resuming the same checkpoint
+ * repeats itself without replaying the stream the saved run started with
+ */
+fn resumed_rng(seed: u64, epoch: usize, episode: Uzz) -> StdRng {
+    let mut bytes = [0; 32];
+    bytes[..8].copy_from_slice(&seed.to_le_bytes());
+    bytes[8..16].copy_from_slice(&(epoch as u64).to_le_bytes());
+    bytes[16..24].copy_from_slice(&episode.to_le_bytes());
+    StdRng::from_seed(bytes)
+}
+
+/* from a random coloring, or from where `checkpoint` stopped; `config` must be `seeded` */
+pub fn search<T: Neighborhood, const C: usize>(
+    config: &SearchConfig,
+    checkpoint: Option<&Checkpoint>
+) -> Result<SearchOutcome, SearchError>
+{
+    let seed = config.seed.expect("a seeded config");
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut strategy = strategy::<T, C>(config);
+    let (start, budget, elapsed) = match checkpoint {
+        None => (start(config, &mut rng), Budget::new(config), 0.0),
+        Some(Checkpoint { elapsed, state, .. }) => {
+            let s: [usize; C] = config.s
+                .clone()
+                .try_into()
+                .expect("S has C entries");
+            let bad_checkpoint = |error: Graph6Error| ConfigError::BadCheckpoint(error.to_string());
+            let roots = state.roots::<T, C>(config.n, s).map_err(bad_checkpoint)?;
+            let Some(start) = roots.first().cloned()
+                else { return Err(ConfigError::BadCheckpoint("no roots".into()).into()) };
+            strategy.restore(roots, &state.visits).map_err(bad_checkpoint)?;
+            rng = resumed_rng(seed, state.epoch, state.episode);
+            (start, Budget::resume(config, state.epoch, state.episode, *elapsed), *elapsed)
+        }
+    };
+    let outcome = strategy.search(start, &budget, &mut Reporter::new(config, elapsed), &mut rng)?;
+    if outcome.witness().is_some() { println!("R{:?} > {} (seed {seed})", config.s, config.n) }
+    Ok(SearchOutcome { seed: Some(seed), ..outcome.map(|best| best.graph().graph6s()) })
+}
+
+struct Search<'a>(&'a SearchConfig, Option<&'a Checkpoint>);
+
+impl Dispatch for Search<'_> {
+    type Output = Result<SearchOutcome, SearchError>;
+    fn run<T: Neighborhood, const C: usize>(self, _n: usize) -> Self::Output {
+        search::<T, C>(self.0, self.1)
+    }
+}
+
+/* the outcome carries the witness when one is found within the budget */
+pub fn search_config(config: &SearchConfig) -> Result<SearchOutcome, SearchError> {
+    config.validate()?;
+    let config = config.clone().seeded();
+    dispatch(config.c(), config.n, Search(&config, None))?
+}
+
+/* continues `checkpoint` under `config`, normally its own with a few flags overridden; the problem,
+   the strategy and how it keys and backs up statistics have to stay as saved */
+pub fn resume_config(config: &SearchConfig, checkpoint: &Checkpoint) -> Result<SearchOutcome, SearchError> {
+    config.validate()?;
+    config.check_resume(&checkpoint.config)?;
+    let config = config.clone().seeded();
+    dispatch(config.c(), config.n, Search(&config, Some(checkpoint)))?
+}
+
+#[cfg(test)]
+pub(crate) mod strategy_tests {
+    use crate::colored_graph::neighborhood::{U8N, U32N};
+    use super::*;
+
+    /* R(3,3) > 5 is easy enough for any strategy within a small budget */
+    pub(crate) fn check_finds_c5(strategy: &mut dyn SearchStrategy<U8N, 2>) {
+        let config = SearchConfig { epochs: 5, episodes: 1_000, ..Default::default() };
+        let mut rng = StdRng::seed_from_u64(1);
+        let outcome = strategy.search(start(&config, &mut rng), &Budget::new(&config), &mut Quiet, &mut rng).unwrap();
+        assert_eq!((outcome.best_count, outcome.stop), (0, StopReason::Witness));
+        /* a random start is sometimes a witness already, which takes no steps */
+        assert!(outcome.stats.epochs >= 1);
+        assert_eq!(outcome.witness().unwrap().graph().verify(&[3, 3], 0), Ok(()));
+    }
+
+    /* sleeps past the deadline once the search has finished */
+    struct Late(Instant);
+
+    impl<T: Neighborhood, const C: usize>
+    Observer<T, C> for Late {
+        fn checkpoint_due(&mut self, finished: bool) -> Option<bool> {
+            if finished { std::thread::sleep(self.0.saturating_duration_since(Instant::now())) }
+            None
+        }
+    }
+
+    /* R(4,4) = 18, so only the budget stops these */
+    fn check_stop_reasons(strategy: fn() -> Box<dyn SearchStrategy<U32N, 2>>) {
+        let mut rng = StdRng::seed_from_u64(6);
+        let run = |config: &SearchConfig, observer: &mut dyn Observer<U32N, 2>, rng: &mut StdRng| {
+            let budget = Budget::new(config);
+            strategy().search(start(config, rng), &budget, observer, rng).unwrap().stop
+        };
+        let config = SearchConfig { s: vec![4, 4], n: 18, epochs: 2, episodes: 5, ..Default::default() };
+        let limited = SearchConfig { time_limit: Some(0.0), ..config.clone() };
+        assert_eq!(run(&limited, &mut Quiet, &mut rng), StopReason::TimeLimit);
+        /* every epoch finishes well within the limit, but the search only returns once it has passed */
+        let limited = SearchConfig { time_limit: Some(0.5), ..config };
+        let deadline = Instant::now() + Duration::from_secs_f64(0.6);
+        assert_eq!(run(&limited, &mut Late(deadline), &mut rng), StopReason::Exhausted);
+    }
+
+    #[test]
+    fn deadlines_after_the_last_epoch_exhaust() {
+        check_stop_reasons(|| Box::new(Mcts::new(&SearchConfig { s: vec![4, 4], n: 18, ..Default::default() })));
+        check_stop_reasons(|| Box::new(Tabu::new(&TabuConfig::default())));
+        check_stop_reasons(|| Box::new(Annealing::new(&AnnealingConfig::default())));
+    }
+
+    #[test]
+    fn mcts_finds_c5() {
+        check_finds_c5(&mut Mcts::new(&SearchConfig::default()));
+        check_finds_c5(&mut Mcts::new(&SearchConfig { threads: 4, ..Default::default() }));
+        for policy in [Policy::Ucb1, Policy::Puct] {
+            let selection = SelectionConfig { policy, explore_decay: 0.9, ..Default::default() };
+            check_finds_c5(&mut Mcts::new(&SearchConfig { selection, ..Default::default() }))
+        }
+        let selection = SelectionConfig { softmax_temperature: 1.0, ..Default::default() };
+        check_finds_c5(&mut Mcts::new(&SearchConfig { selection, ..Default::default() }));
+        for backup in [Backup::Mean, Backup::Max, Backup::Discounted] {
+            let selection = SelectionConfig { backup, ..Default::default() };
+            check_finds_c5(&mut Mcts::new(&SearchConfig { selection, threads: 2, ..Default::default() }))
+        }
+        for schedule in [Length::Constant, Length::Geometric, Length::Luby, Length::Adaptive] {
+            let length = LengthConfig { schedule, ..Default::default() };
+            check_finds_c5(&mut Mcts::new(&SearchConfig { length, ..Default::default() }))
+        }
+    }
+
+    #[test]
+    fn seeded_searches_repeat() {
+        let config = SearchConfig { s: vec![4, 4], n: 17, epochs: 2, episodes: 20, seed: Some(7), ..Default::default() };
+        let run = |strategy: &mut dyn SearchStrategy<U32N, 2>| {
+            let mut rng = StdRng::seed_from_u64(config.seed.unwrap());
+            let outcome = strategy.search(start(&config, &mut rng), &Budget::new(&config), &mut Quiet, &mut rng);
+            outcome.unwrap().best.graph().graph6s()
+        };
+        assert_eq!(run(&mut Mcts::new(&config)), run(&mut Mcts::new(&config)));
+        assert_eq!(run(&mut Tabu::new(&config.tabu)), run(&mut Tabu::new(&config.tabu)));
+        assert_eq!(run(&mut Annealing::new(&config.annealing)), run(&mut Annealing::new(&config.annealing)));
+    }
+
+    #[test]
+    fn outcomes_of_whole_searches() {
+        let output_dir = std::env::temp_dir().join("mcts_ramsey_outcomes");
+        let config = SearchConfig { seed: Some(3), output_dir: output_dir.clone(), ..Default::default() };
+        let outcome = search_config(&config).unwrap();
+        assert_eq!((outcome.best_count, outcome.stop), (0, StopReason::Witness));
+        assert_eq!(outcome.witness(), Some(&outcome.best));
+        assert_eq!(outcome.seed, Some(3));
+        /* R(4, 4) = 18 */
+        let config = SearchConfig { s: vec![4, 4], n: 18, epochs: 2, episodes: 3, output_dir, ..Default::default() };
+        let outcome = search_config(&config).unwrap();
+        assert!(outcome.best_count > 0 && outcome.witness().is_none());
+        assert_eq!((outcome.stop, outcome.stats.epochs, outcome.stats.steps), (StopReason::Exhausted, 2, 6));
+        /* the seed drawn for an unseeded config repeats the search */
+        let rerun = search_config(&SearchConfig { seed: outcome.seed, ..config }).unwrap();
+        assert_eq!((rerun.seed, &rerun.best), (outcome.seed, &outcome.best));
+        let config = SearchConfig { epochs: 0, ..Default::default() };
+        assert_eq!(search_config(&config), Err(SearchError::Config(ConfigError::Zero("EPOCHS"))));
+    }
+
+    #[test]
+    fn resumes_draw_a_new_stream() {
+        let draws = |mut rng: StdRng| (0..4).map(|_| rng.next_u64()).collect::<Vec<_>>();
+        assert_eq!(draws(resumed_rng(7, 2, 30)), draws(resumed_rng(7, 2, 30)));
+        assert_ne!(draws(resumed_rng(7, 2, 30)), draws(StdRng::seed_from_u64(7)));
+        assert_ne!(draws(resumed_rng(7, 2, 30)), draws(resumed_rng(7, 2, 31)));
+        assert_ne!(draws(resumed_rng(7, 2, 30)), draws(resumed_rng(8, 2, 30)));
+    }
+
+    #[test]
+    fn configs_get_seeded() {
+        assert_eq!(SearchConfig { seed: Some(7), ..Default::default() }.seeded().seed, Some(7));
+        assert!(SearchConfig::default().seeded().seed.is_some());
+    }
+}
@@ -0,0 +1,117 @@
+use rand::RngCore;
+
+use crate::prelude::*;
+use crate::{action_matrix::*, colored_graph::neighborhood::Neighborhood, config::TabuConfig};
+use super::{Budget, Observer, SearchError, SearchOutcome, SearchStats, SearchStrategy, checkpoint_best};
+
+/* steepest descent over `ActionMatrix::actions`, where a recolored edge stays put for `tenure` moves */
+pub struct Tabu {
+    tenure: usize,
+    aspiration: bool
+}
+
+impl Tabu {
+    pub fn new(config: &TabuConfig) -> Self {
+        Tabu { tenure: config.tenure, aspiration: config.aspiration }
+    }
+
+    /* the steepest action on a non-tabu edge, or on any edge when it beats `best` */
+    fn next_action<T: Neighborhood, const C: usize>(
+        &self,
+        actions: &mut ActionMatrix<T, C>,
+        tabu_until: &[Uzz],
+        step: Uzz,
+        best: Iyy
+    ) -> Option<Action> {
+        let total = actions.total();
+        let action_queue = actions.actions_mut();
+        let mut popped_actions = vec![];
+
+        let next_action = loop {
+            let Some((&action, &q_ga)) = action_queue.peek()
+                else { break None };
+            if tabu_until[action.1] < step || (self.aspiration && total - q_ga < best) {
+                break Some(action)
+            }
+            popped_actions.push(action_queue.pop().unwrap());
+        };
+
+        while let Some((action, q_ga)) = popped_actions.pop() {
+            action_queue.push(action, q_ga);
+        }
+        next_action
+    }
+}
+
+impl<T: Neighborhood, const C: usize>
+SearchStrategy<T, C> for Tabu {
+    fn search(
+        &mut self,
+        start: ActionMatrix<T, C>,
+        budget: &Budget,
+        observer: &mut dyn Observer<T, C>,
+        _rng: &mut dyn RngCore
+    ) -> Result<SearchOutcome<ActionMatrix<T, C>>, SearchError> {
+        /* at most `tenure` edges are tabu at once, so some edge is always free */
+        let edges = choose_two(start.graph().n());
+        let tenure = self.tenure.min(edges - 1) as Uzz;
+        let mut tabu_until: Vec<Uzz> = vec![0; edges];
+        let mut step: Uzz = 0;
+        let mut best = start.clone();
+        let mut current = start;
+        let mut at = (budget.first_epoch, budget.first_episode);
+        'search: for epoch in budget.epoch_range() {
+            observer.epoch(epoch);
+            for episode in budget.episode_range(epoch) {
+                at = (epoch, episode);
+                checkpoint_best(observer, at, &best, false);
+                if best.total() == 0 || budget.expired() { break 'search }
+                step += 1;
+                let Some(action) = self.next_action(&mut current, &tabu_until, step, best.total())
+                    else { break 'search };
+                current.act(action);
+                tabu_until[action.1] = step + tenure;
+                if current.total() < best.total() {
+                    best = current.clone();
+                    observer.improved(&best);
+                }
+                at = (epoch, episode + 1);
+            }
+        }
+        checkpoint_best(observer, at, &best, true);
+        Ok(SearchOutcome::new(best, budget, SearchStats::new(budget, at.0, step)))
+    }
+}
+
+#[cfg(test)]
+mod tabu_tests {
+    use crate::colored_graph::{ColoredGraph, neighborhood::U8N};
+    use crate::strategy::strategy_tests::check_finds_c5;
+    use super::*;
+
+    #[test]
+    fn tabu_finds_c5() {
+        check_finds_c5(&mut Tabu::new(&TabuConfig::default()));
+        check_finds_c5(&mut Tabu::new(&TabuConfig { tenure: 3, aspiration: false }));
+    }
+
+    #[test]
+    fn skips_tabu_edges() {
+        const E: usize = 10;
+        let mut actions = ActionMatrix::<U8N, 2>::new(ColoredGraph::red(5), [3, 3]);
+        let tabu = Tabu::new(&TabuConfig { tenure: 5, aspiration: false });
+        let (_, first) = tabu.next_action(&mut actions, &[0; E], 1, Iyy::MAX).unwrap();
+
+        let mut tabu_until = [0; E];
+        tabu_until[first] = 1;
+        let (_, second) = tabu.next_action(&mut actions, &tabu_until, 1, Iyy::MAX).unwrap();
+        assert_ne!(first, second);
+        assert_eq!(actions.actions.len(), E);
+        assert_eq!(tabu.next_action(&mut actions, &[1; E], 1, Iyy::MAX), None);
+
+        /* aspiration lets a tabu move through only when it beats the best score */
+        let aspiring = Tabu::new(&TabuConfig { tenure: 5, aspiration: true });
+        assert_eq!(aspiring.next_action(&mut actions, &[1; E], 1, Iyy::MAX).map(|(c, _)| c), Some(1));
+        assert_eq!(aspiring.next_action(&mut actions, &[1; E], 1, 0), None);
+    }
+}